@@ -44,12 +44,6 @@ pub struct ClientState {
 pub trait HostFunctions: light_client_common::HostFunctions {
 	/// Keccak 256 hash function
 	fn keccak_256(input: &[u8]) -> [u8; 32];
-
-	/// Compressed Ecdsa public key recovery from a signature
-	fn secp256k1_ecdsa_recover_compressed(
-		signature: &[u8; 65],
-		value: &[u8; 32],
-	) -> Option<Vec<u8>>;
 }
 
 /// Hash length definition for hashing algorithms used
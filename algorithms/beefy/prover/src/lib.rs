@@ -56,21 +56,22 @@ pub struct Crypto;
 
 impl light_client_common::HostFunctions for Crypto {
 	type BlakeTwo256 = BlakeTwo256;
+	type Keccak256 = sp_runtime::traits::Keccak256;
+	type Sha256 = light_client_common::Sha256Hasher;
+
+	fn secp256k1_ecdsa_recover_compressed(
+		msg: &[u8; 32],
+		signature: &[u8; 65],
+	) -> Result<[u8; 33], light_client_common::EcdsaRecoveryError> {
+		crypto::secp256k1_ecdsa_recover_compressed(signature, msg)
+			.map_err(|_| light_client_common::EcdsaRecoveryError)
+	}
 }
 
 impl HostFunctions for Crypto {
 	fn keccak_256(input: &[u8]) -> [u8; 32] {
 		keccak_256(input)
 	}
-
-	fn secp256k1_ecdsa_recover_compressed(
-		signature: &[u8; 65],
-		value: &[u8; 32],
-	) -> Option<Vec<u8>> {
-		crypto::secp256k1_ecdsa_recover_compressed(signature, value)
-			.ok()
-			.map(|val| val.to_vec())
-	}
 }
 
 /// This contains methods for fetching BEEFY proofs for parachain headers.
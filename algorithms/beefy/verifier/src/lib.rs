@@ -24,8 +24,9 @@ extern crate alloc;
 mod tests;
 
 use beefy_light_client_primitives::{
-	error::BeefyClientError, BeefyNextAuthoritySet, ClientState, HostFunctions, MerkleHasher,
-	MmrUpdateProof, NodesUtils, ParachainsUpdateProof, SignatureWithAuthorityIndex, HASH_LENGTH,
+	error::BeefyClientError, BeefyNextAuthoritySet, ClientState, Hash, HostFunctions, MerkleHasher,
+	MmrUpdateProof, NodesUtils, ParachainsUpdateProof, SignatureWithAuthorityIndex,
+	SignedCommitment, HASH_LENGTH,
 };
 use beefy_primitives::{known_payloads::MMR_ROOT_ID, mmr::MmrLeaf};
 use codec::{Decode, Encode};
@@ -86,56 +87,22 @@ where
 
 	let mmr_root_hash = H256::from_slice(&*mmr_root_vec);
 
-	// Beefy validators sign the keccak_256 hash of the scale encoded commitment
-	let encoded_commitment = mmr_update.signed_commitment.commitment.encode();
-	let commitment_hash = H::keccak_256(&*encoded_commitment);
-
-	let mut authority_indices = Vec::new();
-	let authority_leaves = mmr_update
-		.signed_commitment
-		.signatures
-		.into_iter()
-		.map(|SignatureWithAuthorityIndex { index, signature }| {
-			H::secp256k1_ecdsa_recover_compressed(&signature, &commitment_hash)
-				.and_then(|public_key_bytes| {
-					beefy_primitives::crypto::AuthorityId::from_slice(&public_key_bytes).ok()
-				})
-				.map(|pub_key| {
-					authority_indices.push(index as usize);
-					H::keccak_256(&beefy_mmr::BeefyEcdsaToEthereum::convert(pub_key))
-				})
-				.ok_or(BeefyClientError::InvalidSignature)
-		})
-		.collect::<Result<Vec<_>, BeefyClientError>>()?;
-
-	let mut authorities_changed = false;
-
-	let authorities_merkle_proof =
-		rs_merkle::MerkleProof::<MerkleHasher<H>>::new(mmr_update.authority_proof);
-	// Verify mmr_update.authority_proof against store root hash
-	match validator_set_id {
+	let authorities_changed = match validator_set_id {
 		id if id == current_authority_set.id => {
-			let root_hash = current_authority_set.root;
-			if !authorities_merkle_proof.verify(
-				root_hash.into(),
-				&authority_indices,
-				&authority_leaves,
-				current_authority_set.len as usize,
-			) {
-				return Err(BeefyClientError::InvalidAuthorityProof)
-			}
+			verify_signed_commitment::<H>(
+				current_authority_set,
+				&mmr_update.signed_commitment,
+				mmr_update.authority_proof,
+			)?;
+			false
 		},
 		id if id == next_authority_set.id => {
-			let root_hash = next_authority_set.root;
-			if !authorities_merkle_proof.verify(
-				root_hash.into(),
-				&authority_indices,
-				&authority_leaves,
-				next_authority_set.len as usize,
-			) {
-				return Err(BeefyClientError::InvalidAuthorityProof)
-			}
-			authorities_changed = true;
+			verify_signed_commitment::<H>(
+				next_authority_set,
+				&mmr_update.signed_commitment,
+				mmr_update.authority_proof,
+			)?;
+			true
 		},
 		_ =>
 			return Err(BeefyClientError::AuthoritySetMismatch {
@@ -143,7 +110,7 @@ where
 				next_set_id: next_authority_set.id,
 				commitment_set_id: validator_set_id,
 			}),
-	}
+	};
 
 	let latest_beefy_height = trusted_client_state.latest_beefy_height;
 
@@ -191,6 +158,55 @@ where
 	Ok(trusted_client_state)
 }
 
+/// Verifies that the [`SignedCommitment`] carries enough valid signatures from members of the
+/// given authority set, using the merkle multi-proof of the signers' addresses in the set.
+pub fn verify_signed_commitment<H>(
+	authority_set: &BeefyNextAuthoritySet<H256>,
+	signed_commitment: &SignedCommitment,
+	authority_proof: Vec<Hash>,
+) -> Result<(), BeefyClientError>
+where
+	H: HostFunctions + Clone,
+{
+	if !validate_sigs_against_threshold(authority_set, signed_commitment.signatures.len()) {
+		return Err(BeefyClientError::IncompleteSignatureThreshold)
+	}
+
+	// Beefy validators sign the keccak_256 hash of the scale encoded commitment
+	let encoded_commitment = signed_commitment.commitment.encode();
+	let commitment_hash = H::keccak_256(&*encoded_commitment);
+
+	let mut authority_indices = Vec::new();
+	let authority_leaves = signed_commitment
+		.signatures
+		.iter()
+		.map(|SignatureWithAuthorityIndex { index, signature }| {
+			H::secp256k1_ecdsa_recover_compressed(&commitment_hash, signature)
+				.ok()
+				.and_then(|public_key_bytes| {
+					beefy_primitives::crypto::AuthorityId::from_slice(&public_key_bytes).ok()
+				})
+				.map(|pub_key| {
+					authority_indices.push(*index as usize);
+					H::keccak_256(&beefy_mmr::BeefyEcdsaToEthereum::convert(pub_key))
+				})
+				.ok_or(BeefyClientError::InvalidSignature)
+		})
+		.collect::<Result<Vec<_>, BeefyClientError>>()?;
+
+	let authorities_merkle_proof = rs_merkle::MerkleProof::<MerkleHasher<H>>::new(authority_proof);
+	if !authorities_merkle_proof.verify(
+		authority_set.root.into(),
+		&authority_indices,
+		&authority_leaves,
+		authority_set.len as usize,
+	) {
+		return Err(BeefyClientError::InvalidAuthorityProof)
+	}
+
+	Ok(())
+}
+
 /// Takes the updated client state and parachains headers update proof
 /// and verifies inclusion in mmr
 pub fn verify_parachain_headers<H>(
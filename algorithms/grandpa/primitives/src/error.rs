@@ -13,7 +13,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::Hash;
 use derive_more::{Display, From};
+use sp_consensus_grandpa::AuthorityId;
 
 ///
 #[derive(From, Debug, Display)]
@@ -22,4 +24,54 @@ pub enum Error {
 	Anyhow(anyhow::Error),
 	/// scale codec error
 	Codec(codec::Error),
+	/// GRANDPA justification error
+	Justification(JustificationError),
+}
+
+/// Errors returned while verifying a GRANDPA justification.
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum JustificationError {
+	/// The justification could not be decoded
+	#[display(fmt = "Could not decode grandpa justification")]
+	Decode,
+	/// The justification doesn't finalize the expected block
+	#[display(fmt = "Justification target mismatch: expected {expected:?}, got {got:?}")]
+	TargetMismatch {
+		/// The block hash the justification was expected to finalize
+		expected: Hash,
+		/// The block hash the justification actually finalizes
+		got: Hash,
+	},
+	/// The commit is invalid, or contains duplicate precommits, invalid voters or equivocations
+	#[display(fmt = "Invalid commit in grandpa justification")]
+	InvalidCommit,
+	/// A precommit signature is invalid
+	#[display(fmt = "Invalid signature for precommit from authority {authority:?}")]
+	BadSignature {
+		/// The authority whose precommit signature failed verification
+		authority: AuthorityId,
+	},
+	/// The votes ancestries don't route a precommit target to the commit target
+	#[display(fmt = "Invalid ancestry in grandpa justification")]
+	InvalidAncestry,
+	/// The votes ancestries contain headers that aren't used by any precommit
+	#[display(fmt = "Invalid precommit ancestries in grandpa justification with unused headers")]
+	UnusedHeaders,
+	/// The finalized headers are empty or don't form a contiguous chain
+	#[display(fmt = "Finalized headers don't form a contiguous chain")]
+	InvalidHeaderChain,
+	/// The authority set is empty or has zero total weight
+	#[display(fmt = "Invalid authorities set")]
+	InvalidAuthoritiesSet,
+	/// The justification finalizes a block past a pending authority set change, without the block
+	/// enacting the change having been justified first
+	#[display(
+		fmt = "Justification target {target} skips the authority set change enacted at block {enacted_at}"
+	)]
+	SkippedAuthoritySetChange {
+		/// The block enacting the pending authority set change
+		enacted_at: u32,
+		/// The block number the justification finalizes
+		target: u32,
+	},
 }
@@ -13,7 +13,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{error, Commit, HostFunctions};
+use crate::{error::JustificationError, Commit, Hash, HostFunctions};
 use alloc::collections::{BTreeMap, BTreeSet};
 use anyhow::anyhow;
 use codec::{Decode, Encode};
@@ -23,7 +23,10 @@ use sp_consensus_grandpa::{
 	AuthorityId, AuthorityList, AuthoritySignature, ConsensusLog, Equivocation, RoundNumber,
 	ScheduledChange, SetId, GRANDPA_ENGINE_ID,
 };
-use sp_runtime::{generic::OpaqueDigestItemId, traits::Header as HeaderT};
+use sp_runtime::{
+	generic::OpaqueDigestItemId,
+	traits::{Header as HeaderT, One},
+};
 use sp_std::prelude::*;
 
 /// A GRANDPA justification for block finality, it includes a commit message and
@@ -51,14 +54,18 @@ where
 	H::Number: finality_grandpa::BlockNumberOps,
 {
 	/// Validate the commit and the votes' ancestry proofs.
-	pub fn verify<Host>(&self, set_id: u64, authorities: &AuthorityList) -> Result<(), error::Error>
+	pub fn verify<Host>(
+		&self,
+		set_id: u64,
+		authorities: &AuthorityList,
+	) -> Result<(), JustificationError>
 	where
 		Host: HostFunctions,
 	{
 		// It's safe to assume that the authority list will not contain duplicates,
 		// since this list is extracted from a verified relaychain header.
-		let voters =
-			VoterSet::new(authorities.iter().cloned()).ok_or(anyhow!("Invalid AuthoritiesSet"))?;
+		let voters = VoterSet::new(authorities.iter().cloned())
+			.ok_or(JustificationError::InvalidAuthoritiesSet)?;
 
 		self.verify_with_voter_set::<Host>(set_id, &voters)
 	}
@@ -68,7 +75,7 @@ where
 		&self,
 		set_id: u64,
 		voters: &VoterSet<AuthorityId>,
-	) -> Result<(), error::Error>
+	) -> Result<(), JustificationError>
 	where
 		Host: HostFunctions,
 	{
@@ -82,15 +89,21 @@ where
 					result.num_invalid_voters() > 0 ||
 					result.num_equivocations() > 0
 				{
-					Err(anyhow!("Invalid commit, found one of `duplicate precommits`, `invalid voters`, or `equivocations` {result:?}"))?
+					log::trace!(
+						target: "pallet_ibc",
+						"Invalid commit, found one of `duplicate precommits`, `invalid voters`, or `equivocations` {result:?}"
+					);
+					Err(JustificationError::InvalidCommit)?
 				}
 			},
-			err => {
-				let result = err.map_err(|_| {
-					anyhow!("[verify_with_voter_set] Invalid ancestry while validating commit!")
-				})?;
-				Err(anyhow!("invalid commit in grandpa justification: {result:?}"))?
+			Ok(result) => {
+				log::trace!(
+					target: "pallet_ibc",
+					"Invalid commit in grandpa justification: {result:?}"
+				);
+				Err(JustificationError::InvalidCommit)?
 			},
+			Err(_) => Err(JustificationError::InvalidAncestry)?,
 		}
 
 		// we pick the precommit for the lowest block as the base that
@@ -120,7 +133,8 @@ where
 				&signed.signature,
 				self.round,
 				set_id,
-			)?;
+			)
+			.map_err(|_| JustificationError::BadSignature { authority: signed.id.clone() })?;
 
 			if base_hash == signed.precommit.target_hash {
 				continue
@@ -128,7 +142,7 @@ where
 
 			let route = ancestry_chain
 				.ancestry(base_hash, signed.precommit.target_hash)
-				.map_err(|_| anyhow!("[verify_with_voter_set] Invalid ancestry!"))?;
+				.map_err(|_| JustificationError::InvalidAncestry)?;
 			// ancestry starts from parent hash but the precommit target hash has been
 			// visited
 			visited_hashes.insert(signed.precommit.target_hash);
@@ -141,18 +155,241 @@ where
 			self.votes_ancestries.iter().map(|h: &H| h.hash()).collect();
 
 		if visited_hashes != ancestry_hashes {
-			Err(anyhow!(
-				"invalid precommit ancestries in grandpa justification with unused headers",
-			))?
+			Err(JustificationError::UnusedHeaders)?
 		}
 
 		Ok(())
 	}
 
+	/// Validate the commit and the votes' ancestry proofs, given the standard authority set change
+	/// signalled at a block by the current set which hasn't been enacted yet, if any.
+	///
+	/// Honest voters don't vote past the block enacting a scheduled change, so the justification is
+	/// rejected if it finalizes a block beyond it: the enacting block must be justified first.
+	pub fn verify_with_pending_change<Host>(
+		&self,
+		set_id: u64,
+		voters: &VoterSet<AuthorityId>,
+		pending: Option<(u32, ScheduledChange<u32>)>,
+	) -> Result<(), JustificationError>
+	where
+		Host: HostFunctions,
+		H::Number: Into<u32>,
+	{
+		if let Some((signalled_at, change)) = pending {
+			let enacted_at = signalled_at.saturating_add(change.delay);
+			let target: u32 = self.commit.target_number.into();
+			if target > enacted_at {
+				Err(JustificationError::SkippedAuthoritySetChange { enacted_at, target })?
+			}
+		}
+
+		self.verify_with_voter_set::<Host>(set_id, voters)
+	}
+
 	/// The target block number and hash that this justifications proves finality for.
 	pub fn target(&self) -> (H::Number, H::Hash) {
 		(self.commit.target_number, self.commit.target_hash)
 	}
+
+	/// The exact SCALE encoded length of this justification, used to budget transactions carrying
+	/// it. Unlike `size_hint`, this accounts for the actual size of the headers' digests.
+	pub fn encoded_len(&self) -> usize {
+		Encode::encoded_size(self)
+	}
+
+	/// The number of precommits in the commit, each of which costs a signature verification.
+	pub fn precommit_count(&self) -> usize {
+		self.commit.precommits.len()
+	}
+
+	/// Returns the headers finalized by this justification above `known_finalized`, in ascending
+	/// order and ending with the commit target, taken from `headers`, e.g. a finality proof's
+	/// unknown headers.
+	///
+	/// The route is walked back from the target through the parent hashes, so the side routes
+	/// and descendants of the target that `votes_ancestries` proves precommits for are never
+	/// included, even if `headers` contains them. Fails if `headers` doesn't contain every block
+	/// between `known_finalized` and the target. This should only be called on a verified
+	/// justification.
+	pub fn finalized_headers<'a>(
+		&self,
+		headers: &'a [H],
+		known_finalized: H::Number,
+	) -> Result<Vec<&'a H>, JustificationError> {
+		let headers: BTreeMap<_, _> = headers.iter().map(|h| (h.hash(), h)).collect();
+
+		let mut finalized = vec![];
+		let (mut number, mut hash) = self.target();
+		while number > known_finalized {
+			let header = match headers.get(&hash) {
+				Some(header) if *header.number() == number => *header,
+				_ => Err(JustificationError::InvalidHeaderChain)?,
+			};
+			finalized.push(header);
+			number = number - One::one();
+			hash = *header.parent_hash();
+		}
+		finalized.reverse();
+		Ok(finalized)
+	}
+
+	/// Validate the justification with the authority set held by the tracker and, on success,
+	/// apply the authority set changes signalled by the newly finalized `headers`.
+	///
+	/// `headers` are the canonical headers above the tracker's last finalized block, in ascending
+	/// order and ending with the justification target. The justification for a block enacting a
+	/// change is always verified with the *old* set, the tracker is only rotated afterwards, and a
+	/// justification skipping over that block is rejected. The tracker is left untouched if
+	/// verification fails.
+	pub fn verify_with_tracker<Host>(
+		&self,
+		tracker: &mut AuthoritySetTracker,
+		headers: &[H],
+	) -> Result<Option<SetChange>, JustificationError>
+	where
+		Host: HostFunctions,
+		H: HeaderT<Hash = Hash>,
+		H::Number: Into<u32>,
+	{
+		let target = headers.last().ok_or(JustificationError::InvalidHeaderChain)?;
+		if target.hash() != self.commit.target_hash {
+			Err(JustificationError::TargetMismatch {
+				expected: target.hash(),
+				got: self.commit.target_hash,
+			})?
+		}
+		let number = |header: &H| -> u32 { (*header.number()).into() };
+		let is_contiguous = headers.windows(2).all(|pair| {
+			*pair[1].parent_hash() == pair[0].hash() &&
+				number(&pair[1]) == number(&pair[0]).saturating_add(1)
+		});
+		if !is_contiguous {
+			Err(JustificationError::InvalidHeaderChain)?
+		}
+
+		let voters = VoterSet::new(tracker.authorities.iter().cloned())
+			.ok_or(JustificationError::InvalidAuthoritiesSet)?;
+		let pending = tracker.pending_change().or_else(|| find_pending_change(headers));
+		self.verify_with_pending_change::<Host>(tracker.set_id, &voters, pending)?;
+
+		let mut set_change = None;
+		for header in headers {
+			if let Some(change) = tracker.apply_finalized_header(header) {
+				set_change = Some(change);
+			}
+		}
+		Ok(set_change)
+	}
+}
+
+/// An authority set change that has been enacted by an [`AuthoritySetTracker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetChange {
+	/// Id of the new authority set.
+	pub set_id: SetId,
+	/// The new authority set.
+	pub authorities: AuthorityList,
+	/// Whether the change was signalled as a forced change.
+	pub forced: bool,
+}
+
+/// A change that has been signalled by a finalized header but whose delay hasn't elapsed yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PendingChange {
+	/// Block number of the header signalling the change.
+	signalled_at: u32,
+	/// The signalled change.
+	change: ScheduledChange<u32>,
+	/// Whether the change was signalled as a forced change.
+	forced: bool,
+}
+
+impl PendingChange {
+	/// Block number at which the change is enacted.
+	fn effective_number(&self) -> u32 {
+		self.signalled_at.saturating_add(self.change.delay)
+	}
+}
+
+/// Tracks the current GRANDPA authority set across finalized relay chain headers, using the
+/// scheduled and forced change digests found in those headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthoritySetTracker {
+	set_id: SetId,
+	authorities: AuthorityList,
+	pending: Option<PendingChange>,
+}
+
+impl AuthoritySetTracker {
+	/// Create a tracker starting from the given authority set.
+	pub fn new(set_id: SetId, authorities: AuthorityList) -> Self {
+		Self { set_id, authorities, pending: None }
+	}
+
+	/// Id of the current authority set.
+	pub fn set_id(&self) -> SetId {
+		self.set_id
+	}
+
+	/// The current authority set.
+	pub fn authorities(&self) -> &AuthorityList {
+		&self.authorities
+	}
+
+	/// The standard change signalled by a finalized header which hasn't been enacted yet, along
+	/// with the number of the signalling header. Forced changes aren't reported.
+	pub fn pending_change(&self) -> Option<(u32, ScheduledChange<u32>)> {
+		self.pending
+			.as_ref()
+			.filter(|pending| !pending.forced)
+			.map(|pending| (pending.signalled_at, pending.change.clone()))
+	}
+
+	/// Apply a header that has been finalized by the current authority set, returning the
+	/// authority set change enacted by it, if any.
+	///
+	/// A change signalled with a delay of `n` blocks is enacted once a header at least `n` blocks
+	/// above the signalling header is finalized.
+	pub fn apply_finalized_header<H>(&mut self, header: &H) -> Option<SetChange>
+	where
+		H: HeaderT,
+		H::Number: Into<u32>,
+	{
+		let number: u32 = (*header.number()).into();
+
+		// forced changes take precedence over standard ones, a header should never signal both.
+		let signalled = match find_forced_change(header) {
+			Some((_, change)) => Some((change, true)),
+			None => find_scheduled_change(header).map(|change| (change, false)),
+		};
+		if let Some((change, forced)) = signalled {
+			self.pending = Some(PendingChange {
+				signalled_at: number,
+				change: ScheduledChange {
+					next_authorities: change.next_authorities,
+					delay: change.delay.into(),
+				},
+				forced,
+			});
+		}
+
+		match self.pending.take() {
+			Some(pending) if pending.effective_number() <= number => {
+				self.set_id += 1;
+				self.authorities = pending.change.next_authorities;
+				Some(SetChange {
+					set_id: self.set_id,
+					authorities: self.authorities.clone(),
+					forced: pending.forced,
+				})
+			},
+			pending => {
+				self.pending = pending;
+				None
+			},
+		}
+	}
 }
 
 /// A utility trait implementing `finality_grandpa::Chain` using a given set of headers.
@@ -216,6 +453,24 @@ pub fn find_scheduled_change<H: HeaderT>(header: &H) -> Option<ScheduledChange<H
 	header.digest().convert_first(|l| l.try_to(id).and_then(filter_log))
 }
 
+/// Finds the first standard scheduled change signalled by the given headers, along with the number
+/// of the signalling header.
+pub(crate) fn find_pending_change<H>(headers: &[H]) -> Option<(u32, ScheduledChange<u32>)>
+where
+	H: HeaderT,
+	H::Number: Into<u32>,
+{
+	headers.iter().find_map(|header| {
+		find_scheduled_change(header).map(|change| {
+			let change = ScheduledChange {
+				next_authorities: change.next_authorities,
+				delay: change.delay.into(),
+			};
+			((*header.number()).into(), change)
+		})
+	})
+}
+
 /// Checks the given header for a consensus digest signalling a **forced** scheduled change and
 /// extracts it.
 pub fn find_forced_change<H: HeaderT>(
@@ -314,7 +569,25 @@ where
 mod tests {
 	use super::*;
 	use finality_grandpa::Chain;
-	use sp_runtime::{generic::Header, traits::BlakeTwo256};
+	use sp_core::{crypto::UncheckedFrom, ed25519};
+	use sp_runtime::{
+		generic::{Digest, DigestItem, Header},
+		traits::BlakeTwo256,
+	};
+
+	fn authorities(seed: u8) -> AuthorityList {
+		(0..3)
+			.map(|i| (AuthorityId::from(ed25519::Public::unchecked_from([seed + i; 32])), 1))
+			.collect()
+	}
+
+	fn header_with_log(number: u32, log: Option<ConsensusLog<u32>>) -> Header<u32, BlakeTwo256> {
+		let mut digest = Digest::default();
+		if let Some(log) = log {
+			digest.push(DigestItem::Consensus(GRANDPA_ENGINE_ID, log.encode()));
+		}
+		Header::new(number, Default::default(), Default::default(), Default::default(), digest)
+	}
 
 	#[test]
 	fn test_ancestry_route() {
@@ -343,4 +616,256 @@ mod tests {
 
 		assert_eq!(route, expected);
 	}
+
+	#[test]
+	fn test_authority_set_tracker_scheduled_change() {
+		let mut tracker = AuthoritySetTracker::new(10, authorities(0));
+
+		assert_eq!(tracker.apply_finalized_header(&header_with_log(99, None)), None);
+
+		let change = ScheduledChange { next_authorities: authorities(10), delay: 0 };
+		let header = header_with_log(100, Some(ConsensusLog::ScheduledChange(change)));
+		assert_eq!(
+			tracker.apply_finalized_header(&header),
+			Some(SetChange { set_id: 11, authorities: authorities(10), forced: false })
+		);
+		assert_eq!(tracker.set_id(), 11);
+		assert_eq!(tracker.authorities(), &authorities(10));
+
+		assert_eq!(tracker.apply_finalized_header(&header_with_log(101, None)), None);
+		assert_eq!(tracker.set_id(), 11);
+	}
+
+	#[test]
+	fn test_authority_set_tracker_forced_change() {
+		let mut tracker = AuthoritySetTracker::new(10, authorities(0));
+
+		let change = ScheduledChange { next_authorities: authorities(10), delay: 2 };
+		let header = header_with_log(100, Some(ConsensusLog::ForcedChange(90, change)));
+		assert_eq!(tracker.apply_finalized_header(&header), None);
+		// the delay hasn't elapsed yet, the old set is still in charge.
+		assert_eq!(tracker.apply_finalized_header(&header_with_log(101, None)), None);
+		assert_eq!(tracker.set_id(), 10);
+		assert_eq!(tracker.authorities(), &authorities(0));
+
+		assert_eq!(
+			tracker.apply_finalized_header(&header_with_log(102, None)),
+			Some(SetChange { set_id: 11, authorities: authorities(10), forced: true })
+		);
+		assert_eq!(tracker.set_id(), 11);
+		assert_eq!(tracker.authorities(), &authorities(10));
+	}
+
+	#[test]
+	fn test_justification_errors() {
+		use crate::tests::{chain, signed_justification, TestHost, SET_ID};
+
+		let target = header_with_log(10, None);
+		let (justification, voters) = signed_justification(&target);
+		justification.verify::<TestHost>(SET_ID, &voters).unwrap();
+
+		// empty authority set
+		assert_eq!(
+			justification.verify::<TestHost>(SET_ID, &vec![]),
+			Err(JustificationError::InvalidAuthoritiesSet)
+		);
+
+		// the signer isn't part of the authority set
+		assert_eq!(
+			justification.verify::<TestHost>(SET_ID, &authorities(0)),
+			Err(JustificationError::InvalidCommit)
+		);
+
+		// signed for a different set id
+		assert_eq!(
+			justification.verify::<TestHost>(SET_ID + 1, &voters),
+			Err(JustificationError::BadSignature { authority: voters[0].0.clone() })
+		);
+
+		// votes ancestries contain a header no precommit routes through
+		let mut with_unused_headers = justification.clone();
+		with_unused_headers.votes_ancestries = chain(&target, 1);
+		assert_eq!(
+			with_unused_headers.verify::<TestHost>(SET_ID, &voters),
+			Err(JustificationError::UnusedHeaders)
+		);
+
+		// the justification doesn't finalize the given header
+		let mut tracker = AuthoritySetTracker::new(SET_ID, voters.clone());
+		let other = header_with_log(11, None);
+		assert_eq!(
+			justification.verify_with_tracker::<TestHost>(&mut tracker, &[other.clone()]),
+			Err(JustificationError::TargetMismatch { expected: other.hash(), got: target.hash() })
+		);
+
+		// no finalized headers, or headers that don't form a chain up to the target
+		assert_eq!(
+			justification.verify_with_tracker::<TestHost>(&mut tracker, &[]),
+			Err(JustificationError::InvalidHeaderChain)
+		);
+		assert_eq!(
+			justification.verify_with_tracker::<TestHost>(&mut tracker, &[other, target]),
+			Err(JustificationError::InvalidHeaderChain)
+		);
+		assert_eq!(tracker, AuthoritySetTracker::new(SET_ID, voters));
+	}
+
+	#[test]
+	fn test_verify_with_pending_change() {
+		use crate::tests::{signed_justification, TestHost, SET_ID};
+
+		let target = header_with_log(10, None);
+		let (justification, authorities) = signed_justification(&target);
+		let voters = VoterSet::new(authorities.iter().cloned()).unwrap();
+		let change = |delay| ScheduledChange { next_authorities: authorities.clone(), delay };
+
+		justification
+			.verify_with_pending_change::<TestHost>(SET_ID, &voters, None)
+			.unwrap();
+		// the target is the block enacting the change, or precedes it
+		justification
+			.verify_with_pending_change::<TestHost>(SET_ID, &voters, Some((8, change(2))))
+			.unwrap();
+		justification
+			.verify_with_pending_change::<TestHost>(SET_ID, &voters, Some((9, change(5))))
+			.unwrap();
+
+		// the target is past the block enacting the change
+		assert_eq!(
+			justification.verify_with_pending_change::<TestHost>(
+				SET_ID,
+				&voters,
+				Some((8, change(1)))
+			),
+			Err(JustificationError::SkippedAuthoritySetChange { enacted_at: 9, target: 10 })
+		);
+	}
+
+	#[test]
+	fn test_verify_with_tracker_applies_changes_below_the_target() {
+		use crate::tests::{chain_with_change, signed_justification_by, TestHost, SET_ID};
+
+		let last_known = header_with_log(10, None);
+		let (_, old_set) = signed_justification_by(&last_known, 1, SET_ID);
+		let (_, new_set) = signed_justification_by(&last_known, 2, SET_ID + 1);
+
+		// block 11 signals a change enacted at the target, block 13
+		let change = ScheduledChange { next_authorities: new_set.clone(), delay: 2 };
+		let headers = chain_with_change(&last_known, 3, 0, change);
+		let (justification, _) = signed_justification_by(&headers[2], 1, SET_ID);
+		let mut tracker = AuthoritySetTracker::new(SET_ID, old_set.clone());
+		assert_eq!(
+			justification.verify_with_tracker::<TestHost>(&mut tracker, &headers),
+			Ok(Some(SetChange { set_id: SET_ID + 1, authorities: new_set.clone(), forced: false }))
+		);
+		assert_eq!(tracker.authorities(), &new_set);
+		assert_eq!(tracker.pending_change(), None);
+
+		// block 11 signals a change enacted at block 12, below the target
+		let change = ScheduledChange { next_authorities: new_set.clone(), delay: 1 };
+		let headers = chain_with_change(&last_known, 3, 0, change);
+		let (justification, _) = signed_justification_by(&headers[2], 1, SET_ID);
+		let mut tracker = AuthoritySetTracker::new(SET_ID, old_set.clone());
+		assert_eq!(
+			justification.verify_with_tracker::<TestHost>(&mut tracker, &headers),
+			Err(JustificationError::SkippedAuthoritySetChange { enacted_at: 12, target: 13 })
+		);
+		assert_eq!(tracker, AuthoritySetTracker::new(SET_ID, old_set.clone()));
+
+		// the change is signalled below the target but enacted later, it stays pending
+		let change = ScheduledChange { next_authorities: new_set.clone(), delay: 5 };
+		let headers = chain_with_change(&last_known, 3, 0, change.clone());
+		let (justification, _) = signed_justification_by(&headers[2], 1, SET_ID);
+		let mut tracker = AuthoritySetTracker::new(SET_ID, old_set.clone());
+		assert_eq!(justification.verify_with_tracker::<TestHost>(&mut tracker, &headers), Ok(None));
+		assert_eq!(tracker.authorities(), &old_set);
+		assert_eq!(tracker.pending_change(), Some((11, change)));
+	}
+
+	#[test]
+	fn test_finalized_headers() {
+		let mut headers: Vec<Header<u32, BlakeTwo256>> = vec![header_with_log(10, None)];
+		for number in 11..=14 {
+			let mut header = header_with_log(number, None);
+			header.parent_hash = headers.last().unwrap().hash();
+			headers.push(header);
+		}
+		// a fork at 12, distinct from the canonical block by its state root
+		let mut fork = header_with_log(12, None);
+		fork.parent_hash = headers[1].hash();
+		fork.state_root = sp_core::H256::repeat_byte(1);
+
+		let target = &headers[3];
+		let (mut justification, _) = crate::tests::signed_justification(target);
+		// ancestries of precommits on the fork and on a descendant of the target
+		justification.votes_ancestries = vec![headers[4].clone(), fork.clone()];
+		// a proof's unknown headers, which may include the side routes as well
+		let unknown_headers = vec![
+			headers[1].clone(),
+			fork,
+			headers[2].clone(),
+			headers[3].clone(),
+			headers[4].clone(),
+		];
+
+		assert_eq!(
+			justification.finalized_headers(&unknown_headers, 10),
+			Ok(vec![&headers[1], &headers[2], &headers[3]])
+		);
+		assert_eq!(
+			justification.finalized_headers(&unknown_headers, 11),
+			Ok(vec![&headers[2], &headers[3]])
+		);
+		assert_eq!(justification.finalized_headers(&unknown_headers, 13), Ok(vec![]));
+
+		// block 11 is missing
+		assert_eq!(
+			justification.finalized_headers(&unknown_headers[1..], 10),
+			Err(JustificationError::InvalidHeaderChain)
+		);
+	}
+
+	#[test]
+	fn test_finalized_headers_captured_kusama_header() {
+		let header =
+			Header::<u32, BlakeTwo256>::decode(&mut &crate::tests::KUSAMA_7472481[..]).unwrap();
+		let (mut justification, _) = crate::tests::signed_justification(&header);
+		// as in Kusama's own justifications, the ancestries don't include the target, only the
+		// route from a precommit for one of its descendants
+		let descendants = crate::tests::chain(&header, 2);
+		justification.votes_ancestries = descendants.clone();
+		let unknown_headers = vec![header.clone(), descendants[0].clone(), descendants[1].clone()];
+
+		let known_finalized = header.number - 1;
+		assert_eq!(
+			justification.finalized_headers(&unknown_headers, known_finalized),
+			Ok(vec![&header])
+		);
+		assert_eq!(justification.finalized_headers(&unknown_headers, header.number), Ok(vec![]));
+
+		// the parent of the block isn't known
+		assert_eq!(
+			justification.finalized_headers(&unknown_headers, known_finalized - 1),
+			Err(JustificationError::InvalidHeaderChain)
+		);
+	}
+
+	#[test]
+	fn test_justification_weight_estimates() {
+		let target = header_with_log(10, None);
+		let (mut justification, voters) = crate::tests::signed_justification(&target);
+		assert_eq!(justification.precommit_count(), voters.len());
+		assert_eq!(justification.encoded_len(), justification.encode().len());
+
+		justification.votes_ancestries = crate::tests::chain(&target, 3);
+		assert_eq!(justification.encoded_len(), justification.encode().len());
+
+		// headers carrying digests, whose size isn't known from their type alone
+		let change = ScheduledChange { next_authorities: authorities(0), delay: 1 };
+		let mut header = header_with_log(11, Some(ConsensusLog::ScheduledChange(change)));
+		header.digest.push(DigestItem::PreRuntime(*b"BABE", vec![7; 100]));
+		header.digest.push(DigestItem::Seal(*b"BABE", vec![9; 64]));
+		justification.votes_ancestries = vec![header];
+		assert_eq!(justification.encoded_len(), justification.encode().len());
+	}
 }
@@ -22,11 +22,15 @@
 extern crate alloc;
 
 use alloc::collections::BTreeMap;
+use anyhow::anyhow;
 use codec::{Decode, Encode};
 use core::fmt::Debug;
-use sp_consensus_grandpa::{AuthorityId, AuthorityList, AuthoritySignature};
+use error::JustificationError;
+use finality_grandpa::voter_set::VoterSet;
+use justification::{find_pending_change, AuthoritySetTracker, GrandpaJustification};
+use sp_consensus_grandpa::{AuthorityId, AuthorityList, AuthoritySignature, ScheduledChange};
 use sp_core::{ed25519, sp_std, H256};
-use sp_runtime::traits::Header;
+use sp_runtime::traits::{Header, One};
 use sp_std::prelude::*;
 use sp_storage::StorageKey;
 
@@ -57,6 +61,140 @@ pub struct FinalityProof<H: codec::Codec> {
 	pub unknown_headers: Vec<H>,
 }
 
+/// The result of verifying a [`FinalityProof`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct VerifiedFinality<H: Header> {
+	/// The newly finalized headers, in ascending order and ending with the justification target.
+	pub headers: Vec<H>,
+	/// The block number and hash that the justification proves finality for.
+	pub target: (H::Number, H::Hash),
+}
+
+impl<H> FinalityProof<H>
+where
+	H: Header<Hash = Hash> + codec::Codec,
+	H::Number: finality_grandpa::BlockNumberOps,
+{
+	/// Verify that `unknown_headers` form a contiguous chain from the caller's last known block up
+	/// to [`FinalityProof::block`], and that the justification, validated with the given authority
+	/// set, finalizes that block.
+	///
+	/// `unknown_headers` may be empty if [`FinalityProof::block`] is the last known block.
+	pub fn verify<Host>(
+		&self,
+		last_known: (Hash, H::Number),
+		set_id: u64,
+		authorities: &AuthorityList,
+	) -> Result<VerifiedFinality<H>, error::Error>
+	where
+		Host: HostFunctions,
+	{
+		let justification = self.verify_headers(last_known)?;
+		justification.verify::<Host>(set_id, authorities)?;
+
+		Ok(VerifiedFinality {
+			headers: self.unknown_headers.clone(),
+			target: justification.target(),
+		})
+	}
+
+	/// Same as [`FinalityProof::verify`], but also rejects a justification skipping over the block
+	/// which enacts a standard authority set change, either the `pending` one signalled at a
+	/// previously finalized block, or one signalled by the `unknown_headers`.
+	pub fn verify_with_pending_change<Host>(
+		&self,
+		last_known: (Hash, H::Number),
+		set_id: u64,
+		authorities: &AuthorityList,
+		pending: Option<(u32, ScheduledChange<u32>)>,
+	) -> Result<VerifiedFinality<H>, error::Error>
+	where
+		Host: HostFunctions,
+		H::Number: Into<u32>,
+	{
+		let justification = self.verify_headers(last_known)?;
+		let pending = pending.or_else(|| find_pending_change(&self.unknown_headers));
+		let voters = VoterSet::new(authorities.iter().cloned())
+			.ok_or(JustificationError::InvalidAuthoritiesSet)?;
+		justification.verify_with_pending_change::<Host>(set_id, &voters, pending)?;
+
+		Ok(VerifiedFinality {
+			headers: self.unknown_headers.clone(),
+			target: justification.target(),
+		})
+	}
+
+	/// Checks that `unknown_headers` link the last known block to [`FinalityProof::block`], and
+	/// returns the decoded justification for that block.
+	fn verify_headers(
+		&self,
+		last_known: (Hash, H::Number),
+	) -> Result<GrandpaJustification<H>, error::Error> {
+		let (mut tip_hash, mut tip_number) = last_known;
+		for header in &self.unknown_headers {
+			if *header.parent_hash() != tip_hash || *header.number() != tip_number + One::one() {
+				Err(anyhow!(
+					"Unknown headers don't form a contiguous chain from block {tip_number:?}, found block {:?}",
+					header.number()
+				))?
+			}
+			tip_hash = header.hash();
+			tip_number = *header.number();
+		}
+
+		if tip_hash != self.block {
+			Err(anyhow!("Finality proof block is not the tip of the unknown headers"))?
+		}
+
+		let justification = GrandpaJustification::<H>::decode(&mut &self.justification[..])
+			.map_err(|_| JustificationError::Decode)?;
+		if justification.commit.target_hash != self.block {
+			Err(JustificationError::TargetMismatch {
+				expected: self.block,
+				got: justification.commit.target_hash,
+			})?
+		}
+
+		Ok(justification)
+	}
+}
+
+/// Verifies a sequence of finality proofs, in ascending order, starting from the last known block
+/// and the authority set held by the tracker.
+///
+/// Authority set changes signalled by the finalized headers are applied to the tracker as the
+/// proofs are verified, so every block enacting a standard change has to be finalized by its own
+/// proof before any of its descendants. The tracker is left untouched if any proof fails.
+pub fn verify_finality_proofs<H, Host>(
+	mut last_known: (Hash, H::Number),
+	tracker: &mut AuthoritySetTracker,
+	proofs: &[FinalityProof<H>],
+) -> Result<Vec<VerifiedFinality<H>>, error::Error>
+where
+	H: Header<Hash = Hash> + codec::Codec,
+	H::Number: finality_grandpa::BlockNumberOps + Into<u32>,
+	Host: HostFunctions,
+{
+	let mut next_tracker = tracker.clone();
+	let mut verified = Vec::with_capacity(proofs.len());
+	for proof in proofs {
+		let finality = proof.verify_with_pending_change::<Host>(
+			last_known,
+			next_tracker.set_id(),
+			next_tracker.authorities(),
+			next_tracker.pending_change(),
+		)?;
+		for header in &finality.headers {
+			next_tracker.apply_finalized_header(header);
+		}
+		last_known = (finality.target.1, finality.target.0);
+		verified.push(finality);
+	}
+	*tracker = next_tracker;
+
+	Ok(verified)
+}
+
 /// Previous light client state.
 #[derive(Clone)]
 pub struct ClientState {
@@ -120,3 +258,338 @@ pub fn parachain_header_storage_key(para_id: u32) -> StorageKey {
 	storage_key.extend_from_slice(&encoded_para_id);
 	StorageKey(storage_key)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::Pair;
+	use sp_runtime::traits::BlakeTwo256;
+
+	pub(crate) type TestHeader = sp_runtime::generic::Header<u32, BlakeTwo256>;
+
+	#[derive(Clone, Default, PartialEq, Eq, Debug)]
+	pub(crate) struct TestHost;
+
+	impl light_client_common::HostFunctions for TestHost {
+		type BlakeTwo256 = BlakeTwo256;
+		type Keccak256 = sp_runtime::traits::Keccak256;
+		type Sha256 = light_client_common::Sha256Hasher;
+
+		fn secp256k1_ecdsa_recover_compressed(
+			msg: &[u8; 32],
+			signature: &[u8; 65],
+		) -> Result<[u8; 33], light_client_common::EcdsaRecoveryError> {
+			sp_io::crypto::secp256k1_ecdsa_recover_compressed(signature, msg)
+				.map_err(|_| light_client_common::EcdsaRecoveryError)
+		}
+	}
+
+	impl HostFunctions for TestHost {
+		type Header = TestHeader;
+
+		fn ed25519_verify(sig: &ed25519::Signature, msg: &[u8], pub_key: &ed25519::Public) -> bool {
+			ed25519::Pair::verify(sig, msg, pub_key)
+		}
+
+		fn insert_relay_header_hashes(_headers: &[Hash]) {}
+
+		fn contains_relay_header_hash(_hash: Hash) -> bool {
+			false
+		}
+	}
+
+	pub(crate) const SET_ID: u64 = 3;
+	const ROUND: u64 = 7;
+
+	pub(crate) fn chain(parent: &TestHeader, len: u32) -> Vec<TestHeader> {
+		let mut headers: Vec<TestHeader> = vec![];
+		for _ in 0..len {
+			let parent = headers.last().unwrap_or(parent);
+			headers.push(TestHeader::new(
+				parent.number + 1,
+				Default::default(),
+				Default::default(),
+				parent.hash(),
+				Default::default(),
+			));
+		}
+		headers
+	}
+
+	/// Signs a justification for `target` with a single authority.
+	pub(crate) fn signed_justification(
+		target: &TestHeader,
+	) -> (GrandpaJustification<TestHeader>, AuthorityList) {
+		signed_justification_by(target, 1, SET_ID)
+	}
+
+	/// Signs a justification for `target` with the single authority derived from `seed`, member of
+	/// the authority set `set_id`.
+	pub(crate) fn signed_justification_by(
+		target: &TestHeader,
+		seed: u8,
+		set_id: u64,
+	) -> (GrandpaJustification<TestHeader>, AuthorityList) {
+		let pair = ed25519::Pair::from_seed(&[seed; 32]);
+		let precommit = finality_grandpa::Precommit {
+			target_hash: target.hash(),
+			target_number: target.number,
+		};
+		let message = finality_grandpa::Message::Precommit(precommit.clone());
+		let signature = pair.sign(&(message, ROUND, set_id).encode());
+		let justification = GrandpaJustification::<TestHeader> {
+			round: ROUND,
+			commit: finality_grandpa::Commit {
+				target_hash: target.hash(),
+				target_number: target.number,
+				precommits: vec![finality_grandpa::SignedPrecommit {
+					precommit,
+					signature: signature.into(),
+					id: pair.public().into(),
+				}],
+			},
+			votes_ancestries: vec![],
+		};
+		(justification, vec![(pair.public().into(), 1)])
+	}
+
+	fn finality_proof(
+		target: &TestHeader,
+		unknown_headers: Vec<TestHeader>,
+	) -> (FinalityProof<TestHeader>, AuthorityList) {
+		let (justification, authorities) = signed_justification(target);
+		let proof = FinalityProof {
+			block: target.hash(),
+			justification: justification.encode(),
+			unknown_headers,
+		};
+		(proof, authorities)
+	}
+
+	#[test]
+	fn test_finality_proof_verify() {
+		let last_known = TestHeader::new(
+			10,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let headers = chain(&last_known, 3);
+		let (proof, authorities) = finality_proof(&headers[2], headers.clone());
+
+		let verified =
+			proof.verify::<TestHost>((last_known.hash(), 10), SET_ID, &authorities).unwrap();
+		assert_eq!(verified.headers, headers);
+		assert_eq!(verified.target, (13, headers[2].hash()));
+
+		// wrong authority set id
+		assert!(proof
+			.verify::<TestHost>((last_known.hash(), 10), SET_ID + 1, &authorities)
+			.is_err());
+	}
+
+	#[test]
+	fn test_finality_proof_verify_target_already_known() {
+		let last_known = TestHeader::new(
+			10,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let (proof, authorities) = finality_proof(&last_known, vec![]);
+
+		let verified =
+			proof.verify::<TestHost>((last_known.hash(), 10), SET_ID, &authorities).unwrap();
+		assert!(verified.headers.is_empty());
+		assert_eq!(verified.target, (10, last_known.hash()));
+	}
+
+	#[test]
+	fn test_finality_proof_verify_rejects_invalid_header_chains() {
+		let last_known = TestHeader::new(
+			10,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let headers = chain(&last_known, 3);
+
+		// headers out of order
+		let mut shuffled = headers.clone();
+		shuffled.swap(0, 1);
+		let (proof, authorities) = finality_proof(&headers[2], shuffled);
+		assert!(proof.verify::<TestHost>((last_known.hash(), 10), SET_ID, &authorities).is_err());
+
+		// gap in the chain
+		let gapped = vec![headers[0].clone(), headers[2].clone()];
+		let (proof, authorities) = finality_proof(&headers[2], gapped);
+		assert!(proof.verify::<TestHost>((last_known.hash(), 10), SET_ID, &authorities).is_err());
+
+		// justification for a block that isn't the tip of the header chain
+		let (proof, authorities) = finality_proof(&headers[1], headers.clone());
+		assert!(proof.verify::<TestHost>((last_known.hash(), 10), SET_ID, &authorities).is_err());
+
+		// target already known, but the caller's last known block is different
+		let (proof, authorities) = finality_proof(&headers[0], vec![]);
+		assert!(proof.verify::<TestHost>((last_known.hash(), 10), SET_ID, &authorities).is_err());
+	}
+
+	#[test]
+	fn test_finality_proof_verify_justification_errors() {
+		let last_known = TestHeader::new(
+			10,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let headers = chain(&last_known, 2);
+
+		// undecodable justification
+		let (mut proof, authorities) = finality_proof(&headers[1], headers.clone());
+		proof.justification = vec![1, 2, 3];
+		assert!(matches!(
+			proof.verify::<TestHost>((last_known.hash(), 10), SET_ID, &authorities),
+			Err(error::Error::Justification(JustificationError::Decode))
+		));
+
+		// justification for a different block
+		let (mut proof, authorities) = finality_proof(&headers[1], headers.clone());
+		proof.justification = signed_justification(&headers[0]).0.encode();
+		assert!(matches!(
+			proof.verify::<TestHost>((last_known.hash(), 10), SET_ID, &authorities),
+			Err(error::Error::Justification(JustificationError::TargetMismatch { expected, got }))
+				if expected == headers[1].hash() && got == headers[0].hash()
+		));
+	}
+
+	/// Kusama block #7472481, as captured by smoldot's header tests. It starts a new session, so
+	/// its digest schedules a standard change to a set of 900 authorities, enacted immediately.
+	pub(crate) const KUSAMA_7472481: &[u8] = include_bytes!("fixtures/header-kusama-7472481");
+
+	#[test]
+	fn test_finality_proof_verify_captured_kusama_header() {
+		let header = TestHeader::decode(&mut &KUSAMA_7472481[..]).unwrap();
+		assert_eq!(header.number, 7_472_481);
+		assert_eq!(header.encode(), KUSAMA_7472481);
+		let change = justification::find_scheduled_change(&header).unwrap();
+		assert_eq!(change.next_authorities.len(), 900);
+		assert_eq!(change.delay, 0);
+
+		// Kusama's justification of the block isn't part of the capture, so the block is
+		// finalized by a local authority set instead.
+		let last_known = (header.parent_hash, header.number - 1);
+		let (proof, authorities) = finality_proof(&header, vec![header.clone()]);
+		let verified = proof.verify::<TestHost>(last_known, SET_ID, &authorities).unwrap();
+		assert_eq!(verified.target, (header.number, header.hash()));
+
+		// finalizing the block enacts the change it signals
+		let mut tracker = AuthoritySetTracker::new(SET_ID, authorities.clone());
+		verify_finality_proofs::<_, TestHost>(last_known, &mut tracker, &[proof.clone()]).unwrap();
+		assert_eq!(tracker.set_id(), SET_ID + 1);
+		assert_eq!(tracker.authorities(), &change.next_authorities);
+
+		// without its seal the header no longer hashes to the finalized block
+		let mut unsealed = header.clone();
+		unsealed.digest.pop();
+		let proof = FinalityProof { unknown_headers: vec![unsealed], ..proof };
+		assert!(proof.verify::<TestHost>(last_known, SET_ID, &authorities).is_err());
+
+		// nor does it descend from any other block
+		let (proof, authorities) = finality_proof(&header, vec![header.clone()]);
+		let other = (Hash::repeat_byte(1), header.number - 1);
+		assert!(proof.verify::<TestHost>(other, SET_ID, &authorities).is_err());
+	}
+
+	/// Builds `len` headers on top of `parent`, where the `change_index`-th one signals a scheduled
+	/// authority set change.
+	pub(crate) fn chain_with_change(
+		parent: &TestHeader,
+		len: u32,
+		change_index: usize,
+		change: ScheduledChange<u32>,
+	) -> Vec<TestHeader> {
+		let mut headers = chain(parent, len);
+		headers[change_index].digest.push(sp_runtime::DigestItem::Consensus(
+			sp_consensus_grandpa::GRANDPA_ENGINE_ID,
+			sp_consensus_grandpa::ConsensusLog::ScheduledChange(change).encode(),
+		));
+		for i in change_index + 1..headers.len() {
+			headers[i].parent_hash = headers[i - 1].hash();
+		}
+		headers
+	}
+
+	fn proof_by(
+		target: &TestHeader,
+		unknown_headers: Vec<TestHeader>,
+		seed: u8,
+		set_id: u64,
+	) -> FinalityProof<TestHeader> {
+		FinalityProof {
+			block: target.hash(),
+			justification: signed_justification_by(target, seed, set_id).0.encode(),
+			unknown_headers,
+		}
+	}
+
+	#[test]
+	fn test_finality_proofs_must_not_skip_scheduled_changes() {
+		let last_known = TestHeader::new(
+			10,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let (_, old_set) = signed_justification_by(&last_known, 1, SET_ID);
+		let (_, new_set) = signed_justification_by(&last_known, 2, SET_ID + 1);
+		// block 12 signals a change enacted at block 13
+		let change = ScheduledChange { next_authorities: new_set.clone(), delay: 1 };
+		let headers = chain_with_change(&last_known, 4, 1, change.clone());
+		let start = (last_known.hash(), 10);
+
+		// a single proof finalizing block 14 skips the change block 13
+		let mut tracker = AuthoritySetTracker::new(SET_ID, old_set.clone());
+		let proofs = [proof_by(&headers[3], headers.clone(), 1, SET_ID)];
+		assert!(matches!(
+			verify_finality_proofs::<_, TestHost>(start, &mut tracker, &proofs),
+			Err(error::Error::Justification(JustificationError::SkippedAuthoritySetChange {
+				enacted_at: 13,
+				target: 14
+			}))
+		));
+		assert_eq!(tracker, AuthoritySetTracker::new(SET_ID, old_set.clone()));
+
+		// so does a proof for block 14 after one finalizing the signalling block 12
+		let proofs = [
+			proof_by(&headers[1], headers[..2].to_vec(), 1, SET_ID),
+			proof_by(&headers[3], headers[2..].to_vec(), 1, SET_ID),
+		];
+		assert!(matches!(
+			verify_finality_proofs::<_, TestHost>(start, &mut tracker, &proofs),
+			Err(error::Error::Justification(JustificationError::SkippedAuthoritySetChange {
+				enacted_at: 13,
+				target: 14
+			}))
+		));
+
+		// the change block is finalized by the old set, its descendants by the new one
+		let proofs = [
+			proof_by(&headers[1], headers[..2].to_vec(), 1, SET_ID),
+			proof_by(&headers[2], headers[2..3].to_vec(), 1, SET_ID),
+			proof_by(&headers[3], headers[3..].to_vec(), 2, SET_ID + 1),
+		];
+		let verified = verify_finality_proofs::<_, TestHost>(start, &mut tracker, &proofs).unwrap();
+		assert_eq!(
+			verified.iter().map(|finality| finality.target.0).collect::<Vec<_>>(),
+			vec![12, 13, 14]
+		);
+		assert_eq!(tracker.set_id(), SET_ID + 1);
+		assert_eq!(tracker.authorities(), &new_set);
+		assert_eq!(tracker.pending_change(), None);
+	}
+}
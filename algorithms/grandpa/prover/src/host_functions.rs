@@ -27,6 +27,18 @@ pub struct HostFunctionsProvider;
 
 impl light_client_common::HostFunctions for HostFunctionsProvider {
 	type BlakeTwo256 = BlakeTwo256;
+	type Keccak256 = sp_runtime::traits::Keccak256;
+	type Sha256 = light_client_common::Sha256Hasher;
+
+	fn secp256k1_ecdsa_recover_compressed(
+		msg: &[u8; 32],
+		signature: &[u8; 65],
+	) -> Result<[u8; 33], light_client_common::EcdsaRecoveryError> {
+		sp_core::ecdsa::Signature::from_raw(*signature)
+			.recover_prehashed(msg)
+			.map(|public| public.0)
+			.ok_or(light_client_common::EcdsaRecoveryError)
+	}
 }
 
 impl HostFunctions for HostFunctionsProvider {
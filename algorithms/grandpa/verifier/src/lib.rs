@@ -21,17 +21,14 @@
 
 extern crate alloc;
 
-use alloc::vec;
+use alloc::{collections::BTreeMap, vec};
 use anyhow::anyhow;
 use codec::{Decode, Encode};
-use finality_grandpa::Chain;
 use hash_db::Hasher;
 use light_client_common::state_machine;
 use primitives::{
-	error,
-	justification::{find_scheduled_change, AncestryChain, GrandpaJustification},
-	parachain_header_storage_key, ClientState, HostFunctions, ParachainHeaderProofs,
-	ParachainHeadersWithFinalityProof,
+	error, justification::find_scheduled_change, parachain_header_storage_key, ClientState,
+	HostFunctions, ParachainHeaderProofs, ParachainHeadersWithFinalityProof,
 };
 use sp_core::H256;
 use sp_runtime::traits::Header;
@@ -54,62 +51,42 @@ where
 	Host: HostFunctions,
 	Host::BlakeTwo256: Hasher<Out = H256>,
 {
-	let ParachainHeadersWithFinalityProof { finality_proof, parachain_headers, latest_para_height } =
-		proof;
-
-	// 1. First validate unknown headers.
-	let headers = AncestryChain::<H>::new(&finality_proof.unknown_headers);
-
-	let target = finality_proof
-		.unknown_headers
-		.iter()
-		.max_by_key(|h| *h.number())
-		.ok_or_else(|| anyhow!("Unknown headers can't be empty!"))?;
-
-	// this is illegal
-	if target.hash() != finality_proof.block {
-		Err(anyhow!("Latest finalized block should be highest block in unknown_headers"))?;
-	}
-
-	let justification = GrandpaJustification::<H>::decode(&mut &finality_proof.justification[..])?;
-
-	if justification.commit.target_hash != finality_proof.block {
-		Err(anyhow!("Justification target hash and finality proof block hash mismatch"))?;
-	}
-
-	let from = client_state.latest_relay_hash;
-
-	let base = finality_proof
+	let ParachainHeadersWithFinalityProof {
+		mut finality_proof,
+		parachain_headers,
+		latest_para_height,
+	} = proof;
+
+	// 1. Verify that the unknown headers descend from the latest known relay chain block and that
+	// the justification, signed by the current authority set, finalizes their tip. Headers up to
+	// the latest known block have been finalized before, so they're skipped.
+	finality_proof.unknown_headers.sort_by_key(|header| *header.number());
+	finality_proof
 		.unknown_headers
+		.retain(|header| *header.number() > client_state.latest_relay_height);
+	let finality = finality_proof.verify::<Host>(
+		(client_state.latest_relay_hash, client_state.latest_relay_height),
+		client_state.current_set_id,
+		&client_state.current_authorities,
+	)?;
+	let target = finality
+		.headers
+		.last()
+		.ok_or_else(|| anyhow!("Finality proof doesn't finalize any new relay chain block"))?;
+	let finalized = finality
+		.headers
 		.iter()
-		.min_by_key(|h| *h.number())
-		.ok_or_else(|| anyhow!("Unknown headers can't be empty!"))?;
-
-	if base.number() < &client_state.latest_relay_height {
-		headers.ancestry(base.hash(), client_state.latest_relay_hash).map_err(|_| {
-			anyhow!(
-				"[verify_parachain_headers_with_grandpa_finality_proof] Invalid ancestry (base -> latest relay block)!"
-			)
-		})?;
-	}
-
-	let mut finalized = headers.ancestry(from, target.hash()).map_err(|_| {
-		anyhow!("[verify_parachain_headers_with_grandpa_finality_proof] Invalid ancestry!")
-	})?;
-	finalized.sort();
+		.map(|header| (header.hash(), header))
+		.collect::<BTreeMap<_, _>>();
 
-	// 2. verify justification.
-	justification.verify::<Host>(client_state.current_set_id, &client_state.current_authorities)?;
-
-	// 3. verify state proofs of parachain headers in finalized relay chain headers.
+	// 2. verify state proofs of parachain headers in finalized relay chain headers.
 	let mut para_heights = vec![];
 	for (hash, proofs) in parachain_headers {
-		if finalized.binary_search(&hash).is_err() {
+		let relay_chain_header = match finalized.get(&hash) {
+			Some(header) => header,
 			// seems relay hash isn't in the finalized chain.
-			continue
-		}
-		let relay_chain_header =
-			headers.header(&hash).expect("Headers have been checked by AncestryChain; qed");
+			None => continue,
+		};
 
 		let ParachainHeaderProofs { extrinsic_proof, extrinsic, state_proof } = proofs;
 		let proof = StorageProof::new(state_proof);
@@ -138,7 +115,7 @@ where
 		.map_err(|_| anyhow!("Invalid extrinsic proof"))?;
 	}
 
-	// 4. set new client state, optionally rotating authorities
+	// 3. set new client state, optionally rotating authorities
 	client_state.latest_relay_hash = target.hash();
 	client_state.latest_relay_height = (*target.number()).into();
 	if let Some(max_height) = para_heights.into_iter().max() {
@@ -147,7 +124,7 @@ where
 		}
 		client_state.latest_para_height = max_height;
 	}
-	if let Some(scheduled_change) = find_scheduled_change::<H>(&target) {
+	if let Some(scheduled_change) = find_scheduled_change::<H>(target) {
 		client_state.current_set_id += 1;
 		client_state.current_authorities = scheduled_change.next_authorities;
 	}
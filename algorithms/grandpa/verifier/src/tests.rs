@@ -22,10 +22,12 @@ use grandpa_prover::{
 use hyperspace_core::substrate::DefaultConfig as PolkadotConfig;
 use polkadot_core_primitives::Header;
 use primitives::{
-	justification::GrandpaJustification, FinalityProof, ParachainHeadersWithFinalityProof,
+	justification::{find_scheduled_change, GrandpaJustification},
+	ClientState, FinalityProof, ParachainHeadersWithFinalityProof,
 };
 use serde::{Deserialize, Serialize};
-use sp_core::H256;
+use sp_core::{ed25519, Pair, H256};
+use sp_runtime::traits::Header as _;
 use std::time::Duration;
 use subxt::{
 	config::substrate::{BlakeTwo256, SubstrateHeader},
@@ -151,3 +153,83 @@ async fn follow_grandpa_justifications() {
 		println!("========= Successfully verified grandpa justification =========");
 	}
 }
+
+/// Kusama block #7472481, which schedules a change to a set of 900 authorities.
+const KUSAMA_7472481: &[u8] = include_bytes!("../../primitives/src/fixtures/header-kusama-7472481");
+
+/// Signs a justification for `target` with a single local authority, as Kusama's own justification
+/// of the captured block isn't available.
+fn signed_justification(target: &Header, set_id: u64) -> (Justification, ed25519::Pair) {
+	let pair = ed25519::Pair::from_seed(&[1; 32]);
+	let precommit =
+		finality_grandpa::Precommit { target_hash: target.hash(), target_number: target.number };
+	let message = finality_grandpa::Message::Precommit(precommit.clone());
+	let signature = pair.sign(&(message, 1u64, set_id).encode());
+	let justification = Justification {
+		round: 1,
+		commit: finality_grandpa::Commit {
+			target_hash: target.hash(),
+			target_number: target.number,
+			precommits: vec![finality_grandpa::SignedPrecommit {
+				precommit,
+				signature: signature.into(),
+				id: pair.public().into(),
+			}],
+		},
+		votes_ancestries: vec![],
+	};
+	(justification, pair)
+}
+
+#[test]
+fn verify_captured_kusama_header() {
+	let header = Header::decode(&mut &KUSAMA_7472481[..]).unwrap();
+	let (justification, pair) = signed_justification(&header, 5);
+	let client_state = ClientState {
+		current_authorities: vec![(pair.public().into(), 1)],
+		current_set_id: 5,
+		latest_relay_height: header.number - 1,
+		latest_relay_hash: header.parent_hash,
+		latest_para_height: 0,
+		para_id: 2000,
+	};
+	let proof = ParachainHeadersWithFinalityProof {
+		finality_proof: FinalityProof {
+			block: header.hash(),
+			justification: justification.encode(),
+			unknown_headers: vec![header.clone()],
+		},
+		parachain_headers: Default::default(),
+		latest_para_height: 0,
+	};
+
+	let new_client_state = verify_parachain_headers_with_grandpa_finality_proof::<
+		Header,
+		HostFunctionsProvider,
+	>(client_state.clone(), proof.clone())
+	.unwrap();
+	assert_eq!(new_client_state.latest_relay_hash, header.hash());
+	assert_eq!(new_client_state.latest_relay_height, 7_472_481);
+	assert_eq!(new_client_state.current_set_id, 6);
+	assert_eq!(
+		new_client_state.current_authorities,
+		find_scheduled_change(&header).unwrap().next_authorities
+	);
+
+	// the header doesn't descend from a different latest relay chain block
+	let other_client_state =
+		ClientState { latest_relay_hash: H256::repeat_byte(1), ..client_state.clone() };
+	verify_parachain_headers_with_grandpa_finality_proof::<Header, HostFunctionsProvider>(
+		other_client_state,
+		proof.clone(),
+	)
+	.unwrap_err();
+
+	// the justification has to be signed by the client's authority set
+	let next_set_client_state = ClientState { current_set_id: 6, ..client_state };
+	verify_parachain_headers_with_grandpa_finality_proof::<Header, HostFunctionsProvider>(
+		next_set_client_state,
+		proof,
+	)
+	.unwrap_err();
+}
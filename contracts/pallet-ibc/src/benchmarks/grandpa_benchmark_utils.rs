@@ -34,10 +34,11 @@ pub fn generate_finality_proof(
 	// Build timestamp extrinsic with proof
 	let mut para_db = MemoryDB::<BlakeTwo256>::default();
 
-	let mut timestamp_extrinsic =
-		(1u8, 0u8, Compact(GRANDPA_UPDATE_TIMESTAMP.saturating_mul(10000))).encode();
-	timestamp_extrinsic.insert(0, 0);
-	timestamp_extrinsic.insert(0, 0);
+	// a bare v4 extrinsic calling `Timestamp::set`, prefixed with its length
+	let timestamp_extrinsic =
+		(4u8, 1u8, 0u8, Compact(GRANDPA_UPDATE_TIMESTAMP.saturating_mul(10000)))
+			.encode()
+			.encode();
 	let key = Compact(0u64).encode();
 	let extrinsics_root = {
 		let mut root = Default::default();
@@ -158,6 +159,9 @@ pub fn generate_finality_proof(
 		para_id,
 		current_set_id: set_id,
 		current_authorities: authorities.into_iter().map(|authority| (authority, 100)).collect(),
+		commitment_prefix: b"ibc/".to_vec(),
+		custom_trusting_period: None,
+		max_consensus_states: None,
 		_phantom: Default::default(),
 	};
 
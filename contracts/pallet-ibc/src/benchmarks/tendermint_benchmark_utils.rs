@@ -297,6 +297,7 @@ pub(crate) fn create_mock_beefy_client_state(
 		para_id: 2087,
 		authority: Default::default(),
 		next_authority_set: Default::default(),
+		custom_trusting_period: None,
 		_phantom: Default::default(),
 	};
 
@@ -319,6 +320,9 @@ pub(crate) fn create_mock_grandpa_client_state() -> (
 		para_id: 2087,
 		current_set_id: 0,
 		current_authorities: vec![],
+		commitment_prefix: b"ibc/".to_vec(),
+		custom_trusting_period: None,
+		max_consensus_states: None,
 		_phantom: Default::default(),
 	};
 
@@ -431,6 +431,13 @@ where
 					Err(ICS02Error::implementation_specific(format!("client state is frozen")))?
 				}
 
+				if client_state.commitment_prefix != T::PalletPrefix::get() {
+					log::warn!(target : "pallet_ibc", "configured commitment prefix {:?} differs from provided in data {:?}", T::PalletPrefix::get(), client_state.commitment_prefix);
+					Err(ICS02Error::implementation_specific(format!(
+						"commitment prefix mis-match"
+					)))?
+				}
+
 				(client_state.relay_chain, client_state.para_id, client_state.latest_para_height)
 			},
 			client => Err(ICS02Error::unknown_client_type(format!("{}", client.client_type())))?,
@@ -42,7 +42,7 @@ use ics10_grandpa::{
 };
 use ics11_beefy::{
 	client_message::BEEFY_CLIENT_MESSAGE_TYPE_URL, client_state::BEEFY_CLIENT_STATE_TYPE_URL,
-	consensus_state::BEEFY_CONSENSUS_STATE_TYPE_URL,
+	consensus_state::BEEFY_CONSENSUS_STATE_TYPE_URL, misbehaviour::BEEFY_MISBEHAVIOUR_TYPE_URL,
 };
 use prost::Message;
 use sp_core::{crypto::ByteArray, ed25519, H256};
@@ -198,18 +198,22 @@ impl grandpa_client_primitives::HostFunctions for HostFunctionsManager {
 
 impl light_client_common::HostFunctions for HostFunctionsManager {
 	type BlakeTwo256 = BlakeTwo256;
+	type Keccak256 = sp_runtime::traits::Keccak256;
+	type Sha256 = light_client_common::Sha256Hasher;
+
+	fn secp256k1_ecdsa_recover_compressed(
+		msg: &[u8; 32],
+		signature: &[u8; 65],
+	) -> Result<[u8; 33], light_client_common::EcdsaRecoveryError> {
+		sp_io::crypto::secp256k1_ecdsa_recover_compressed(signature, msg)
+			.map_err(|_| light_client_common::EcdsaRecoveryError)
+	}
 }
 
 impl beefy_client_primitives::HostFunctions for HostFunctionsManager {
 	fn keccak_256(input: &[u8]) -> [u8; 32] {
 		sp_io::hashing::keccak_256(input)
 	}
-
-	fn secp256k1_ecdsa_recover_compressed(signature: &[u8; 65], msg: &[u8; 32]) -> Option<Vec<u8>> {
-		sp_io::crypto::secp256k1_ecdsa_recover_compressed(signature, msg)
-			.ok()
-			.map(|pub_key| pub_key.to_vec())
-	}
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, ClientDef)]
@@ -423,11 +427,16 @@ impl TryFrom<Any> for AnyClientMessage {
 					ics10_grandpa::client_message::Misbehaviour::decode_vec(&value.value)
 						.map_err(ics02_client::error::Error::decode_raw_header)?,
 				))),
-			// TODO: beefy header, misbehaviour impl From<Any>
+			// TODO: beefy header impl From<Any>
 			BEEFY_CLIENT_MESSAGE_TYPE_URL => Ok(Self::Beefy(
 				ics11_beefy::client_message::ClientMessage::decode_vec(&value.value)
 					.map_err(ics02_client::error::Error::decode_raw_header)?,
 			)),
+			BEEFY_MISBEHAVIOUR_TYPE_URL =>
+				Ok(Self::Beefy(ics11_beefy::client_message::ClientMessage::Misbehaviour(
+					ics11_beefy::misbehaviour::Misbehaviour::decode_vec(&value.value)
+						.map_err(ics02_client::error::Error::decode_raw_header)?,
+				))),
 			TENDERMINT_CLIENT_MESSAGE_TYPE_URL => Ok(Self::Tendermint(
 				ics07_tendermint::client_message::ClientMessage::decode_vec(&value.value)
 					.map_err(ics02_client::error::Error::decode_raw_header)?,
@@ -484,9 +493,15 @@ impl From<AnyClientMessage> for Any {
 					value: m.encode_vec().expect("encode_vec failed"),
 				},
 			},
-			AnyClientMessage::Beefy(msg) => Any {
-				type_url: BEEFY_CLIENT_MESSAGE_TYPE_URL.to_string(),
-				value: msg.encode_vec().expect("encode_vec failed"),
+			AnyClientMessage::Beefy(msg) => match msg {
+				ics11_beefy::client_message::ClientMessage::Misbehaviour(m) => Any {
+					type_url: BEEFY_MISBEHAVIOUR_TYPE_URL.to_string(),
+					value: m.encode_vec().expect("encode_vec failed"),
+				},
+				msg => Any {
+					type_url: BEEFY_CLIENT_MESSAGE_TYPE_URL.to_string(),
+					value: msg.encode_vec().expect("encode_vec failed"),
+				},
 			},
 			AnyClientMessage::Tendermint(msg) => Any {
 				type_url: TENDERMINT_CLIENT_MESSAGE_TYPE_URL.to_string(),
@@ -776,6 +776,16 @@ macro_rules! chains {
 				}
 			}
 
+			async fn requires_own_transaction(&self, msg: &Any) -> Result<bool, Self::Error> {
+				match self {
+					$(
+						$(#[$($meta)*])*
+						Self::$name(chain) => chain.requires_own_transaction(msg).await.map_err(AnyError::$name),
+					)*
+					Self::Wasm(c) => c.inner.requires_own_transaction(msg).await,
+				}
+			}
+
 			async fn finality_notifications(
 				&self,
 			) -> Result<Pin<Box<dyn Stream<Item = Self::FinalityEvent> + Send + Sync>>, Self::Error> {
@@ -22,6 +22,22 @@ pub async fn flush_message_batch(
 	metrics: Option<&MetricsHandler>,
 	sink: &impl Chain,
 ) -> Result<(), anyhow::Error> {
+	// messages that need a transaction of their own are usually client updates, which the rest of
+	// the batch depends on, so they go first.
+	let mut batch = Vec::with_capacity(msgs.len());
+	for msg in msgs {
+		if sink.requires_own_transaction(&msg).await? {
+			log::info!(target: "hyperspace", "Submitting {} in a transaction of its own", msg.type_url);
+			sink.submit(vec![msg]).await?;
+		} else {
+			batch.push(msg);
+		}
+	}
+	if batch.is_empty() {
+		return Ok(())
+	}
+	let msgs = batch;
+
 	let block_max_weight = sink.block_max_weight();
 	let batch_weight = sink.estimate_weight(msgs.clone()).await?;
 
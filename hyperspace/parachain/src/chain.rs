@@ -24,7 +24,10 @@ use ibc::{
 	core::{
 		ics02_client::{
 			events::UpdateClient,
-			msgs::{update_client::MsgUpdateAnyClient, ClientMsg},
+			msgs::{
+				update_client::{MsgUpdateAnyClient, TYPE_URL as UPDATE_CLIENT_TYPE_URL},
+				ClientMsg,
+			},
 		},
 		ics26_routing::msgs::Ics26Envelope,
 	},
@@ -55,6 +58,7 @@ use subxt::{
 	},
 	events::Phase,
 };
+use tendermint_proto::Protobuf;
 use tokio::time::sleep;
 use transaction_payment_rpc::TransactionPaymentApiClient;
 use transaction_payment_runtime_api::RuntimeDispatchInfo;
@@ -136,6 +140,28 @@ where
 		Ok(dispatch_info.weight.ref_time())
 	}
 
+	async fn requires_own_transaction(&self, msg: &Any) -> Result<bool, Self::Error> {
+		let justification = match grandpa_justification(msg) {
+			Some(justification) => justification,
+			None => return Ok(false),
+		};
+		// the runtime weighs the update by the justification's precommits
+		let weight = self.estimate_weight(vec![msg.clone()]).await?;
+		log::debug!(
+			target: "hyperspace_parachain",
+			"GRANDPA justification of {} bytes with {} precommits weighs {}",
+			justification.encoded_len(),
+			justification.precommit_count(),
+			weight
+		);
+		Ok(fills_half_a_block(
+			justification.encoded_len() as u64,
+			weight,
+			self.max_block_length,
+			self.block_max_weight(),
+		))
+	}
+
 	async fn finality_notifications(
 		&self,
 	) -> Result<
@@ -517,3 +543,64 @@ where
 		Ok(())
 	}
 }
+
+/// The GRANDPA justification carried by `msg`, if it's a client update with a GRANDPA header.
+fn grandpa_justification(msg: &Any) -> Option<GrandpaJustification> {
+	if msg.type_url != UPDATE_CLIENT_TYPE_URL {
+		return None
+	}
+	let msg = MsgUpdateAnyClient::<LocalClientTypes>::decode_vec(&msg.value).ok()?;
+	match msg.client_message.unpack_recursive_into() {
+		AnyClientMessage::Grandpa(ClientMessage::Header(header)) =>
+			GrandpaJustification::decode(&mut &*header.finality_proof.justification).ok(),
+		_ => None,
+	}
+}
+
+/// Whether a message of `length` bytes and `weight` takes up more than half of the block's length
+/// or weight. Two such messages can't share a block, so each of them needs its own transaction.
+fn fills_half_a_block(
+	length: u64,
+	weight: u64,
+	max_block_length: u64,
+	block_max_weight: u64,
+) -> bool {
+	length.saturating_mul(2) > max_block_length || weight.saturating_mul(2) > block_max_weight
+}
+
+#[cfg(test)]
+mod tests {
+	use super::fills_half_a_block;
+
+	const MAX_BLOCK_LENGTH: u64 = 5 * 1024 * 1024;
+	const BLOCK_MAX_WEIGHT: u64 = 2_000_000_000_000;
+
+	#[test]
+	fn small_justifications_are_batched() {
+		assert!(!fills_half_a_block(10_000, 1_000_000_000, MAX_BLOCK_LENGTH, BLOCK_MAX_WEIGHT));
+		assert!(!fills_half_a_block(
+			MAX_BLOCK_LENGTH / 2,
+			BLOCK_MAX_WEIGHT / 2,
+			MAX_BLOCK_LENGTH,
+			BLOCK_MAX_WEIGHT
+		));
+	}
+
+	#[test]
+	fn large_justifications_get_their_own_transaction() {
+		// too long
+		assert!(fills_half_a_block(
+			MAX_BLOCK_LENGTH / 2 + 1,
+			1_000_000_000,
+			MAX_BLOCK_LENGTH,
+			BLOCK_MAX_WEIGHT
+		));
+		// too heavy
+		assert!(fills_half_a_block(
+			10_000,
+			BLOCK_MAX_WEIGHT / 2 + 1,
+			MAX_BLOCK_LENGTH,
+			BLOCK_MAX_WEIGHT
+		));
+	}
+}
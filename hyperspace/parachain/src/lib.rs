@@ -40,7 +40,9 @@ use frame_support::Serialize;
 use serde::Deserialize;
 
 use crate::{
-	finality_protocol::FinalityProtocol, signer::ExtrinsicSigner, utils::fetch_max_extrinsic_weight,
+	finality_protocol::FinalityProtocol,
+	signer::ExtrinsicSigner,
+	utils::{fetch_max_block_length, fetch_max_extrinsic_weight},
 };
 use beefy_light_client_primitives::{ClientState, MmrUpdateProof};
 use beefy_prover::Prover;
@@ -119,6 +121,8 @@ pub struct ParachainClient<T: light_client_common::config::Config> {
 	pub ss58_version: Ss58AddressFormat,
 	/// the maximum extrinsic weight allowed by this client
 	pub max_extrinsic_weight: u64,
+	/// the maximum block length, in bytes, available to normal extrinsics
+	pub max_block_length: u64,
 	/// Finality protocol to use, eg Beefy, Grandpa
 	pub finality_protocol: FinalityProtocol,
 	/// Common relayer data
@@ -213,6 +217,7 @@ where
 		let relay_client = subxt::OnlineClient::from_rpc_client(relay_ws_client.clone()).await?;
 
 		let max_extrinsic_weight = fetch_max_extrinsic_weight(&para_client).await?;
+		let max_block_length = fetch_max_block_length(&para_client).await?;
 
 		let temp_dir = PathBuf::from("/tmp/keystore");
 		let key_store: KeystorePtr = Arc::new(LocalKeystore::open(temp_dir, None).unwrap());
@@ -256,6 +261,7 @@ where
 			key_store,
 			key_type_id,
 			max_extrinsic_weight,
+			max_block_length,
 			para_ws_client,
 			relay_ws_client,
 			ss58_version: Ss58AddressFormat::from(config.ss58_version),
@@ -455,6 +461,22 @@ where
 			.expect("Client Id should be defined")
 			.clone()
 	}
+
+	/// Fetches the commitment prefix of the parachain's ibc store from the ibc pallet's
+	/// constants, and checks that it's the one the client is configured with.
+	pub fn runtime_commitment_prefix(&self) -> Result<Vec<u8>, Error> {
+		let address = subxt::dynamic::constant("Ibc", "PalletPrefix");
+		let constant = self.para_client.constants().at(&address)?;
+		let commitment_prefix = Vec::<u8>::decode(&mut constant.encoded())?;
+		if commitment_prefix.is_empty() || commitment_prefix != self.commitment_prefix {
+			return Err(Error::Custom(format!(
+				"Runtime commitment prefix {:?} is empty or differs from the configured one {:?}",
+				String::from_utf8_lossy(&commitment_prefix),
+				String::from_utf8_lossy(&self.commitment_prefix)
+			)))
+		}
+		Ok(commitment_prefix)
+	}
 }
 
 impl<T: light_client_common::config::Config + Send + Sync> ParachainClient<T>
@@ -531,6 +553,7 @@ where
 				para_id: self.para_id,
 				authority: beefy_state.current_authorities,
 				next_authority_set: beefy_state.next_authorities,
+				custom_trusting_period: None,
 				_phantom: Default::default(),
 			};
 			// we can't use the genesis block to construct the initial state.
@@ -587,6 +610,8 @@ where
 		};
 		let api = self.relay_client.storage();
 		let para_client_api = self.para_client.storage();
+		// the client only accepts proofs under the prefix of the parachain's ibc store
+		let commitment_prefix = self.runtime_commitment_prefix()?;
 		loop {
 			let light_client_state = prover
 				.initialize_client_state()
@@ -625,6 +650,7 @@ where
 			client_state.latest_para_height = block_number;
 			client_state.para_id = self.para_id;
 			client_state.latest_relay_height = light_client_state.latest_relay_height;
+			client_state.commitment_prefix = commitment_prefix.clone();
 
 			let subxt_block_number: subxt::rpc::types::BlockNumber = block_number.into();
 			let block_hash =
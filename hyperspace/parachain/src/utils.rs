@@ -17,7 +17,7 @@ use beefy_light_client_primitives::{ClientState, MmrUpdateProof};
 use beefy_primitives::known_payloads::MMR_ROOT_ID;
 use codec::Decode;
 use frame_support::pallet_prelude::{DispatchClass, Weight};
-use frame_system::limits::BlockWeights;
+use frame_system::limits::{BlockLength, BlockWeights};
 use sp_core::H256;
 
 pub fn get_updated_client_state(
@@ -59,3 +59,18 @@ pub async fn fetch_max_extrinsic_weight<T: light_client_common::config::Config>(
 		.unwrap_or(Weight::from_parts(u64::MAX, 0));
 	Ok(max_extrinsic_weight.ref_time())
 }
+
+/// Fetch the maximum block length, in bytes, available to normal extrinsics from a substrate node
+/// with the given client.
+pub async fn fetch_max_block_length<T: light_client_common::config::Config>(
+	client: &subxt::OnlineClient<T>,
+) -> Result<u64, Error> {
+	let metadata = client.rpc().metadata().await?;
+	let block_length = metadata
+		.pallet_by_name("System")
+		.expect("System pallet should exist")
+		.constant_by_name("BlockLength")
+		.expect("constant BlockLength should exist");
+	let length = BlockLength::decode(&mut &block_length.value()[..])?;
+	Ok(*length.max.get(DispatchClass::Normal) as u64)
+}
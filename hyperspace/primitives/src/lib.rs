@@ -533,6 +533,12 @@ pub trait Chain:
 	/// Should return an estimate of the weight of a batch of messages.
 	async fn estimate_weight(&self, msg: Vec<Any>) -> Result<u64, Self::Error>;
 
+	/// Should return whether the message must be submitted in a transaction of its own, e.g.
+	/// because it takes up most of a block. By default messages are batched freely.
+	async fn requires_own_transaction(&self, _msg: &Any) -> Result<bool, Self::Error> {
+		Ok(false)
+	}
+
 	/// Return a stream that yields when new [`IbcEvents`] are ready to be queried.
 	async fn finality_notifications(
 		&self,
@@ -68,11 +68,15 @@ where
 	let mut para_db = MemoryDB::<BlakeTwo256>::default();
 
 	// TODO: how to construct timestamp extrinsic via metadata?
-	let mut timestamp_extrinsic =
-		(1u8, 0u8, Compact(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis()))
-			.encode();
-	timestamp_extrinsic.insert(0, 0);
-	timestamp_extrinsic.insert(0, 0);
+	// a bare v4 extrinsic calling `Timestamp::set`, prefixed with its length
+	let timestamp_extrinsic = (
+		4u8,
+		1u8,
+		0u8,
+		Compact(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64),
+	)
+		.encode()
+		.encode();
 	let key = Compact(0u32).encode();
 	let extrinsics_root = {
 		let mut root = Default::default();
@@ -764,7 +764,7 @@ impl State {
 		quote! {
 			impl #impl_generics #client_def_trait for #this #ty_generics #where_clause {
 				type ClientMessage = #any_client_message;
-				type ClientState = #any_client_state::<#ty_generics>;
+				type ClientState = #any_client_state #ty_generics;
 				type ConsensusState = #any_consensus_state;
 
 				#fn_verify_client_message
@@ -334,12 +334,25 @@ where
 
 	/// Triggers the advancing of the host chain, by extending the history of blocks (or headers).
 	pub fn advance_host_chain_height(&mut self) {
+		self.advance_time(self.block_time)
+	}
+
+	/// Advances the host chain by one block, produced `duration` after the latest block.
+	pub fn advance_time(&mut self, duration: Duration) {
+		let latest_block = self.history.last().expect("history cannot be empty");
+		let timestamp = latest_block.timestamp().add(duration).unwrap();
+		self.advance_host_chain_height_with_timestamp(timestamp)
+	}
+
+	/// Advances the host chain by one block with the given timestamp. The timestamp isn't checked
+	/// against the latest block, so it may go back in time.
+	pub fn advance_host_chain_height_with_timestamp(&mut self, timestamp: Timestamp) {
 		let latest_block = self.history.last().expect("history cannot be empty");
 		let new_block = <C as HostBlockType>::HostBlock::generate_block(
 			self.host_chain_id.clone(),
 			self.host_chain_type,
 			latest_block.height().increment().revision_height,
-			latest_block.timestamp().add(self.block_time).unwrap(),
+			timestamp,
 		);
 
 		// Append the new header at the tip of the history.
@@ -14,13 +14,22 @@
 // limitations under the License.
 
 //! State verification functions
+//!
+//! Checks of storage proofs against a state root: values of the top-level trie, values of a child
+//! trie (e.g. the ibc store of a parachain) and parachain heads stored in a relay chain's state.
 
 use alloc::{boxed::Box, collections::BTreeMap, string::String, vec::Vec};
-use codec::Decode;
+use anyhow::anyhow;
+use codec::{Decode, Encode};
 use core::fmt::Debug;
 use hash_db::{HashDB, Hasher, EMPTY_PREFIX};
+use ibc::core::ics23_commitment::commitment::CommitmentRoot;
+use sp_core::{
+	hashing::{twox_128, twox_64},
+	H256,
+};
 use sp_storage::ChildInfo;
-use sp_trie::{KeySpacedDB, LayoutV0, StorageProof, Trie, TrieDBBuilder};
+use sp_trie::{KeySpacedDB, LayoutV0, NodeCodec, StorageProof, Trie, TrieDBBuilder, TrieLayout};
 
 #[derive(Debug, derive_more::From, derive_more::Display)]
 pub enum Error<H>
@@ -30,14 +39,39 @@ where
 {
 	#[display(fmt = "Trie Error: {:?}", _0)]
 	Trie(Box<sp_trie::TrieError<LayoutV0<H>>>),
-	#[display(fmt = "Error verifying key: {key:?}, Expected: {expected:?}, Got: {got:?}")]
-	ValueMismatch { key: Option<String>, expected: Option<Vec<u8>>, got: Option<Vec<u8>> },
+	#[display(fmt = "Child Trie Error: {:?}", _0)]
+	#[from(ignore)]
+	ChildTrie(Box<sp_trie::TrieError<LayoutV0<H>>>),
+	#[display(
+		fmt = "Error verifying key: {key:?}, Expected: {expected:?}, Got: {got:?}, Child root: {child_root:?}"
+	)]
+	ValueMismatch {
+		key: Option<String>,
+		expected: Option<Vec<u8>>,
+		got: Option<Vec<u8>>,
+		child_root: H::Out,
+	},
 	#[display(fmt = "Couldn't find child root in proof")]
 	ChildRootNotFound,
 	#[display(fmt = "Invalid Proof")]
 	InvalidProof,
 }
 
+impl<H> Error<H>
+where
+	H: Hasher,
+	H::Out: Debug,
+{
+	/// Returns which of the two sub-proofs of a child trie proof failed: the proof of the child
+	/// trie root in the top-level trie, or the proof of the values in the child trie.
+	pub fn sub_proof(&self) -> &'static str {
+		match self {
+			Error::Trie(_) | Error::ChildRootNotFound | Error::InvalidProof => "top-level trie",
+			Error::ChildTrie(_) | Error::ValueMismatch { .. } => "child trie",
+		}
+	}
+}
+
 /// Lifted directly from [`sp-state-machine::read_child_proof_check`](https://github.com/paritytech/substrate/blob/b27c470eaff379f512d1dec052aff5d551ed3b03/primitives/state-machine/src/lib.rs#L1138-L1161)
 pub fn read_child_proof_check<H, I>(
 	root: H::Out,
@@ -49,9 +83,32 @@ where
 	H: Hasher,
 	H::Out: Debug,
 	I: IntoIterator<Item = (Vec<u8>, Option<Vec<u8>>)>,
+{
+	read_child_proof_check_with_layout::<LayoutV0<H>, H, I>(root, proof, child_info, items)
+}
+
+/// Same as [`read_child_proof_check`], but reads the proof with the given trie layout, e.g.
+/// [`LayoutV1`](sp_trie::LayoutV1) for chains on the newer state version.
+pub fn read_child_proof_check_with_layout<L, H, I>(
+	root: H::Out,
+	proof: StorageProof,
+	child_info: ChildInfo,
+	items: I,
+) -> Result<(), Error<H>>
+where
+	L: TrieLayout<Hash = H, Codec = NodeCodec<H>>,
+	H: Hasher,
+	H::Out: Debug,
+	I: IntoIterator<Item = (Vec<u8>, Option<Vec<u8>>)>,
 {
 	let memory_db = proof.into_memory_db::<H>();
-	let trie = TrieDBBuilder::<LayoutV0<H>>::new(&memory_db, &root).build();
+
+	if !memory_db.contains(&root, EMPTY_PREFIX) {
+		Err(Error::InvalidProof)?
+	}
+
+	// the child trie root is stored in the top-level trie under the child's prefixed storage key
+	let trie = TrieDBBuilder::<L>::new(&memory_db, &root).build();
 	let child_root = trie
 		.get(child_info.prefixed_storage_key().as_slice())?
 		.map(|r| {
@@ -65,16 +122,20 @@ where
 		.ok_or(Error::<H>::ChildRootNotFound)?;
 
 	let child_db = KeySpacedDB::new(&memory_db, child_info.keyspace());
-	let child_trie = TrieDBBuilder::<LayoutV0<H>>::new(&child_db, &child_root).build();
+	let child_trie = TrieDBBuilder::<L>::new(&child_db, &child_root).build();
 
 	for (key, value) in items {
-		let recovered = child_trie.get(&key)?.and_then(|val| Decode::decode(&mut &val[..]).ok());
+		let recovered = child_trie
+			.get(&key)
+			.map_err(Error::ChildTrie)?
+			.and_then(|val| Decode::decode(&mut &val[..]).ok());
 
 		if recovered != value {
 			Err(Error::ValueMismatch {
 				key: String::from_utf8(key).ok(),
 				expected: value,
 				got: recovered,
+				child_root,
 			})?
 		}
 	}
@@ -110,3 +171,46 @@ where
 
 	Ok(result)
 }
+
+/// Storage key of the head of parachain `para_id` in the relay chain's `Paras::Heads` map, i.e.
+/// `twox_128("Paras") ++ twox_128("Heads") ++ twox_64_concat(para_id)`.
+pub fn parachain_head_storage_key(para_id: u32) -> Vec<u8> {
+	let para_id = para_id.encode();
+	let mut key = twox_128(b"Paras").to_vec();
+	key.extend(twox_128(b"Heads"));
+	key.extend(twox_64(&para_id));
+	key.extend(para_id);
+	key
+}
+
+/// Verifies that the encoded `header` is the head of parachain `para_id` stored in the relay chain
+/// state with the given root, using the proof nodes of the relay chain's state trie.
+pub fn verify_parachain_header_proof<H>(
+	relay_root: &CommitmentRoot,
+	para_id: u32,
+	header: &[u8],
+	proof: &[Vec<u8>],
+) -> Result<(), anyhow::Error>
+where
+	H: Hasher<Out = H256>,
+{
+	if relay_root.as_bytes().len() != 32 {
+		return Err(anyhow!("invalid relay chain root length: {}", relay_root.as_bytes().len()))
+	}
+	let root = H256::from_slice(relay_root.as_bytes());
+	let key = parachain_head_storage_key(para_id);
+	let head = read_proof_check::<H, _>(&root, StorageProof::new(proof.to_vec()), [&key])
+		.map_err(|err| {
+			anyhow!(
+				"Failed to verify header proof of parachain {para_id}, root: {root:?}, error: \
+				 {err}"
+			)
+		})?
+		.remove(&key)
+		.flatten()
+		.ok_or_else(|| anyhow!("No head of parachain {para_id} in the relay chain state"))?;
+	if head != header {
+		return Err(anyhow!("The head of parachain {para_id} doesn't match the given header"))
+	}
+	Ok(())
+}
@@ -670,13 +670,15 @@ pub mod test_util {
 
 	use crate::{
 		client_state::ClientState,
-		mock::{AnyClientState, Crypto},
+		mock::{AnyClientState, MockHostFunctions},
 	};
 	use ibc::core::{ics02_client::height::Height, ics24_host::identifier::ChainId};
 
-	pub fn get_dummy_tendermint_client_state(tm_header: Header) -> AnyClientState {
+	pub fn get_dummy_tendermint_client_state<H: MockHostFunctions>(
+		tm_header: Header,
+	) -> AnyClientState<H> {
 		AnyClientState::Tendermint(
-			ClientState::<Crypto>::new(
+			ClientState::<H>::new(
 				ChainId::from(tm_header.chain_id.clone()),
 				Default::default(),
 				Duration::from_secs(64000),
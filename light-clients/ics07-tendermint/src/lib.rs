@@ -86,31 +86,277 @@ mod tests {
 		mock::{AnyClientState, AnyConsensusState, MockClientTypes},
 	};
 
-	use crate::{client_message::ClientMessage, mock::AnyClientMessage};
+	use crate::{
+		client_def::TendermintClient,
+		client_message::{ClientMessage, Header, Misbehaviour},
+		mock::{
+			context::with_tendermint_client,
+			host::{MockHostBlock, MockHostType},
+			AnyClient, AnyClientMessage, Crypto,
+		},
+		HostFunctionsProvider,
+	};
 	use ibc::{
 		core::{
 			ics02_client::{
-				context::ClientReader,
+				client_def::ClientDef,
+				client_state::ClientState as _,
+				context::{ClientKeeper, ClientReader},
+				error::{Error as Ics02Error, ErrorDetail},
 				handler::{dispatch, ClientResult},
 				msgs::{
 					create_client::MsgCreateAnyClient, update_client::MsgUpdateAnyClient, ClientMsg,
 				},
 				trust_threshold::TrustThreshold,
 			},
-			ics23_commitment::specs::ProofSpecs,
-			ics24_host::identifier::ClientId,
+			ics03_connection::{
+				connection::{Counterparty, State as ConnectionState},
+				context::ConnectionReader,
+				msgs::{conn_open_init::MsgConnectionOpenInit, ConnectionMsg},
+			},
+			ics23_commitment::{commitment::CommitmentPrefix, specs::ProofSpecs},
+			ics24_host::identifier::{ChainId, ClientId, ConnectionId},
+			ics26_routing::msgs::Ics26Envelope,
 		},
 		events::IbcEvent,
 		handler::HandlerOutput,
 		mock::context::MockContext,
 		prelude::*,
 		test_utils::get_dummy_account_id,
+		timestamp::Timestamp,
 		Height,
 	};
-	use ibc_proto::ibc::core::client::v1::{MsgCreateClient, MsgUpdateClient};
+	use ibc_proto::{
+		google::protobuf::Any,
+		ibc::core::client::v1::{MsgCreateClient, MsgUpdateClient},
+	};
+	use ics10_grandpa::{
+		client_message::RelayChainHeader,
+		client_state::{ClientState as GrandpaClientState, GRANDPA_CLIENT_STATE_TYPE_URL},
+		consensus_state::ConsensusState as GrandpaConsensusState,
+	};
+	use ics11_beefy::client_state::{ClientState as BeefyClientState, BEEFY_CLIENT_STATE_TYPE_URL};
+	use light_client_common::RelayChain;
+	use sp_core::{ed25519, Pair, H256};
 	use std::time::Duration;
+	use tendermint::{
+		crypto::{
+			signature::{Error as SignatureError, Verifier},
+			Sha256,
+		},
+		merkle::{MerkleHash, NonIncremental, HASH_SIZE},
+		Hash, PublicKey, Signature,
+	};
+	use tendermint_light_client_verifier::operations::CommitValidator;
+	use tendermint_proto::Protobuf;
 	use test_log::test;
 
+	/// A second host, hashing and verifying signatures through `sp_core` rather than the
+	/// rust-crypto implementations used by [`Crypto`].
+	#[derive(Clone, Debug, Default, PartialEq, Eq)]
+	struct SubstrateCrypto;
+
+	impl ics23::HostFunctionsProvider for SubstrateCrypto {
+		fn sha2_256(message: &[u8]) -> [u8; 32] {
+			sp_core::hashing::sha2_256(message)
+		}
+
+		fn sha2_512(_message: &[u8]) -> [u8; 64] {
+			unimplemented!()
+		}
+
+		fn sha2_512_truncated(_message: &[u8]) -> [u8; 32] {
+			unimplemented!()
+		}
+
+		fn sha3_512(_message: &[u8]) -> [u8; 64] {
+			unimplemented!()
+		}
+
+		fn ripemd160(_message: &[u8]) -> [u8; 20] {
+			unimplemented!()
+		}
+	}
+
+	impl Sha256 for SubstrateCrypto {
+		fn digest(data: impl AsRef<[u8]>) -> [u8; HASH_SIZE] {
+			sp_core::hashing::sha2_256(data.as_ref())
+		}
+	}
+
+	impl MerkleHash for SubstrateCrypto {
+		fn empty_hash(&mut self) -> [u8; HASH_SIZE] {
+			NonIncremental::<Self>::default().empty_hash()
+		}
+
+		fn leaf_hash(&mut self, bytes: &[u8]) -> [u8; HASH_SIZE] {
+			NonIncremental::<Self>::default().leaf_hash(bytes)
+		}
+
+		fn inner_hash(&mut self, left: [u8; HASH_SIZE], right: [u8; HASH_SIZE]) -> [u8; HASH_SIZE] {
+			NonIncremental::<Self>::default().inner_hash(left, right)
+		}
+	}
+
+	impl Verifier for SubstrateCrypto {
+		fn verify(
+			pubkey: PublicKey,
+			msg: &[u8],
+			signature: &Signature,
+		) -> Result<(), SignatureError> {
+			let pubkey = pubkey.ed25519().ok_or(SignatureError::UnsupportedKeyType)?;
+			let pubkey = ed25519::Public::try_from(pubkey.as_bytes())
+				.map_err(|_| SignatureError::MalformedPublicKey)?;
+			let signature = ed25519::Signature::try_from(signature.as_bytes())
+				.map_err(|_| SignatureError::MalformedSignature)?;
+			ed25519::Pair::verify(&signature, msg, &pubkey)
+				.then_some(())
+				.ok_or(SignatureError::VerificationFailed)
+		}
+	}
+
+	impl CommitValidator for SubstrateCrypto {}
+
+	impl HostFunctionsProvider for SubstrateCrypto {}
+
+	impl light_client_common::HostFunctions for SubstrateCrypto {
+		type BlakeTwo256 = sp_runtime::traits::BlakeTwo256;
+		type Keccak256 = sp_runtime::traits::Keccak256;
+		type Sha256 = light_client_common::Sha256Hasher;
+
+		fn secp256k1_ecdsa_recover_compressed(
+			msg: &[u8; 32],
+			signature: &[u8; 65],
+		) -> Result<[u8; 33], light_client_common::EcdsaRecoveryError> {
+			<Crypto as light_client_common::HostFunctions>::secp256k1_ecdsa_recover_compressed(
+				msg, signature,
+			)
+		}
+	}
+
+	impl grandpa_client_primitives::HostFunctions for SubstrateCrypto {
+		type Header = RelayChainHeader;
+
+		fn ed25519_verify(sig: &ed25519::Signature, msg: &[u8], pub_key: &ed25519::Public) -> bool {
+			ed25519::Pair::verify(sig, msg, pub_key)
+		}
+
+		fn insert_relay_header_hashes(headers: &[H256]) {
+			<Crypto as grandpa_client_primitives::HostFunctions>::insert_relay_header_hashes(
+				headers,
+			)
+		}
+
+		fn contains_relay_header_hash(hash: H256) -> bool {
+			<Crypto as grandpa_client_primitives::HostFunctions>::contains_relay_header_hash(hash)
+		}
+	}
+
+	impl beefy_light_client_primitives::HostFunctions for SubstrateCrypto {
+		fn keccak_256(input: &[u8]) -> [u8; 32] {
+			sp_core::keccak_256(input)
+		}
+	}
+
+	/// A context on `mockgaiaA-1` with a tendermint client tracking `mockgaiaB-1`, whose latest
+	/// consensus state is a block at height 10 produced a minute before the host's current time.
+	fn tm_client_context(
+		client_id: &ClientId,
+		max_clock_drift: Duration,
+	) -> MockContext<MockClientTypes> {
+		let ctx = MockContext::<MockClientTypes>::new(
+			ChainId::new("mockgaiaA".to_string(), 1),
+			MockHostType::Mock,
+			5,
+			Height::new(1, 5),
+		);
+		let trusted_block = MockHostBlock::generate_tm_block(
+			ChainId::new("mockgaiaB".to_string(), 1),
+			10,
+			(ctx.host_timestamp() - Duration::from_secs(60)).unwrap(),
+		);
+		with_tendermint_client(ctx, client_id, trusted_block, max_clock_drift)
+	}
+
+	/// Updates the client with the counterparty block at height 11, produced at `timestamp`.
+	fn update_tm_client(
+		ctx: &MockContext<MockClientTypes>,
+		client_id: &ClientId,
+		timestamp: Timestamp,
+	) -> Result<HandlerOutput<ClientResult<MockContext<MockClientTypes>>>, Ics02Error> {
+		let light_block = MockHostBlock::generate_tm_block(
+			ChainId::new("mockgaiaB".to_string(), 1),
+			11,
+			timestamp,
+		);
+		let header = Header { trusted_height: Height::new(1, 10), ..light_block.into() };
+		let msg = MsgUpdateAnyClient::new(
+			client_id.clone(),
+			AnyClientMessage::Tendermint(ClientMessage::Header(header)),
+			get_dummy_account_id(),
+		);
+		dispatch(ctx, ClientMsg::UpdateClient(msg))
+	}
+
+	/// A context on `mockgaiaA-1` with a grandpa client tracking parachain 2000, whose latest
+	/// consensus state is the parachain block at height 10.
+	fn grandpa_client_context(client_id: &ClientId) -> MockContext<MockClientTypes> {
+		let mut ctx = MockContext::<MockClientTypes>::new(
+			ChainId::new("mockgaiaA".to_string(), 1),
+			MockHostType::Mock,
+			5,
+			Height::new(1, 5),
+		);
+		let height = Height::new(2000, 10);
+		let client_state = GrandpaClientState::<Crypto> {
+			para_id: 2000,
+			latest_para_height: 10,
+			..Default::default()
+		};
+		let consensus_state = GrandpaConsensusState::new(
+			vec![0; 32],
+			tendermint::Time::from_unix_timestamp(0, 0).unwrap(),
+		);
+		ctx.store_client_state(client_id.clone(), AnyClientState::Grandpa(client_state))
+			.unwrap();
+		ctx.store_consensus_state(
+			client_id.clone(),
+			height,
+			AnyConsensusState::Grandpa(consensus_state),
+		)
+		.unwrap();
+		ctx
+	}
+
+	/// Starts a connection handshake from `client_id` towards `counterparty_client_id`.
+	fn conn_open_init(
+		ctx: &mut MockContext<MockClientTypes>,
+		client_id: &ClientId,
+		counterparty_client_id: &ClientId,
+	) {
+		let msg = MsgConnectionOpenInit {
+			client_id: client_id.clone(),
+			counterparty: Counterparty::new(
+				counterparty_client_id.clone(),
+				None,
+				CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap(),
+			),
+			version: None,
+			delay_period: Duration::from_secs(0),
+			signer: get_dummy_account_id(),
+		};
+		ctx.deliver(Ics26Envelope::Ics3Msg(ConnectionMsg::ConnectionOpenInit(msg)))
+			.unwrap();
+	}
+
+	/// A misbehaviour made of two headers at the same height committing to different blocks.
+	fn dummy_misbehaviour(client_id: ClientId) -> Misbehaviour {
+		let header1 = get_dummy_ics07_header();
+		let mut header2 = header1.clone();
+		header2.signed_header.commit.block_id.hash = Hash::Sha256([0xff; 32]);
+		Misbehaviour { client_id, header1, header2 }
+	}
+
 	#[test]
 	fn msg_create_client_serialization() {
 		let signer = get_dummy_account_id();
@@ -210,4 +456,259 @@ mod tests {
 		assert_eq!(msg, msg_back);
 		assert_eq!(raw, raw_back);
 	}
+
+	#[test]
+	fn msg_submit_misbehaviour_serialization() {
+		let client_id: ClientId = "tendermint".parse().unwrap();
+		let signer = get_dummy_account_id();
+
+		let msg = MsgUpdateAnyClient::<MockContext<MockClientTypes>>::new(
+			client_id.clone(),
+			AnyClientMessage::Tendermint(ClientMessage::Misbehaviour(dummy_misbehaviour(
+				client_id,
+			))),
+			signer,
+		);
+		let raw = MsgUpdateClient::from(msg.clone());
+		let msg_back = MsgUpdateAnyClient::try_from(raw.clone()).unwrap();
+		let raw_back = MsgUpdateClient::from(msg_back.clone());
+		assert_eq!(msg, msg_back);
+		assert_eq!(raw, raw_back);
+	}
+
+	#[test]
+	fn test_tm_misbehaviour_freezes_client() {
+		let client_id = ClientId::new(&ClientState::<()>::client_type(), 0).unwrap();
+		let mut ctx = tm_client_context(&client_id, Duration::from_secs(3));
+		let now = ctx.host_timestamp();
+		let client = AnyClient::Tendermint(TendermintClient::default());
+		let client_state = ctx.client_state(&client_id).unwrap();
+		let submit_misbehaviour = |ctx: &MockContext<MockClientTypes>, header1, header2| {
+			let misbehaviour = Misbehaviour { client_id: client_id.clone(), header1, header2 };
+			let msg = MsgUpdateAnyClient::new(
+				client_id.clone(),
+				AnyClientMessage::Tendermint(ClientMessage::Misbehaviour(misbehaviour)),
+				get_dummy_account_id(),
+			);
+			dispatch(ctx, ClientMsg::UpdateClient(msg))
+		};
+
+		// two blocks at height 11, both signed by the trusted validators
+		let header_at = |timestamp| {
+			let light_block = MockHostBlock::generate_tm_block(
+				ChainId::new("mockgaiaB".to_string(), 1),
+				11,
+				timestamp,
+			);
+			Header { trusted_height: Height::new(1, 10), ..light_block.into() }
+		};
+		let header1 = header_at(now);
+		let header2 = header_at((now - Duration::from_secs(1)).unwrap());
+		assert_ne!(
+			header1.signed_header.commit.block_id.hash,
+			header2.signed_header.commit.block_id.hash
+		);
+
+		// the same header twice is not a misbehaviour
+		let duplicate = AnyClientMessage::Tendermint(ClientMessage::Misbehaviour(Misbehaviour {
+			client_id: client_id.clone(),
+			header1: header1.clone(),
+			header2: header1.clone(),
+		}));
+		client
+			.verify_client_message(&ctx, client_id.clone(), client_state.clone(), duplicate.clone())
+			.unwrap();
+		assert!(!client
+			.check_for_misbehaviour(&ctx, client_id.clone(), client_state, duplicate)
+			.unwrap());
+
+		// a conflicting header whose commit isn't signed by the validators is rejected
+		let mut forged = header2.clone();
+		forged.signed_header.commit.block_id.hash = Hash::Sha256([0xff; 32]);
+		let err = submit_misbehaviour(&ctx, header1.clone(), forged).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::HeaderVerificationFailure(_)), "{err}");
+		assert_eq!(ctx.client_state(&client_id).unwrap().frozen_height(), None);
+
+		let HandlerOutput { result, events, .. } =
+			submit_misbehaviour(&ctx, header1, header2).unwrap();
+		assert!(matches!(events.as_slice(), [IbcEvent::ClientMisbehaviour(_)]), "{events:?}");
+		ctx.store_client_result(result).unwrap();
+
+		let client_state = ctx.client_state(&client_id).unwrap();
+		assert_eq!(client_state.frozen_height(), Some(Height::new(1, 11)));
+		let err = update_tm_client(&ctx, &client_id, now).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::ClientFrozen(_)), "{err}");
+	}
+
+	#[test]
+	fn test_tm_update_rejected_after_trusting_period() {
+		let client_id = ClientId::new(&ClientState::<()>::client_type(), 0).unwrap();
+		let mut ctx = tm_client_context(&client_id, Duration::from_secs(3));
+		let header_time = ctx.host_timestamp();
+		let trusting_period = match ctx.client_state(&client_id).unwrap() {
+			AnyClientState::Tendermint(client_state) => client_state.trusting_period,
+			_ => unreachable!(),
+		};
+
+		update_tm_client(&ctx, &client_id, header_time).unwrap();
+
+		ctx.advance_time(trusting_period + Duration::from_secs(1));
+		let err = update_tm_client(&ctx, &client_id, header_time).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::HeaderNotWithinTrustPeriod(_)), "{err}");
+	}
+
+	#[test]
+	fn test_tm_update_within_clock_drift() {
+		let client_id = ClientId::new(&ClientState::<()>::client_type(), 0).unwrap();
+		let ctx = tm_client_context(&client_id, Duration::from_secs(10));
+		let now = ctx.host_timestamp();
+
+		// the counterparty's clock is ahead of the host's, but within the tolerated drift
+		let output =
+			update_tm_client(&ctx, &client_id, (now + Duration::from_secs(5)).unwrap()).unwrap();
+		match output.result {
+			ClientResult::Update(result) =>
+				assert_eq!(result.client_state.latest_height(), Height::new(1, 11)),
+			_ => panic!("expected result of type ClientResult::Update"),
+		}
+
+		// past the tolerated drift, the header is from the future
+		let err = update_tm_client(&ctx, &client_id, (now + Duration::from_secs(20)).unwrap())
+			.unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::HeaderVerificationFailure(_)), "{err}");
+	}
+
+	#[test]
+	fn test_tm_update_rejects_host_time_going_backwards() {
+		let client_id = ClientId::new(&ClientState::<()>::client_type(), 0).unwrap();
+		let mut ctx = tm_client_context(&client_id, Duration::from_secs(3));
+		let updated_at = ctx.host_timestamp();
+
+		// the next host block is produced before the client's last update
+		ctx.advance_host_chain_height_with_timestamp(
+			(updated_at - Duration::from_secs(60)).unwrap(),
+		);
+		let err = update_tm_client(&ctx, &client_id, updated_at).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::InvalidConsensusStateTimestamp(_)), "{err}");
+	}
+
+	#[test]
+	fn test_conn_open_init_between_tendermint_and_grandpa_clients() {
+		let tm_client_id = ClientId::new(&ClientState::<()>::client_type(), 0).unwrap();
+		let grandpa_client_id =
+			ClientId::new(&GrandpaClientState::<Crypto>::client_type(), 0).unwrap();
+
+		// the grandpa-backed chain opens a connection towards the tendermint-backed one
+		let mut grandpa_ctx = grandpa_client_context(&grandpa_client_id);
+		conn_open_init(&mut grandpa_ctx, &grandpa_client_id, &tm_client_id);
+
+		// and the tendermint-backed chain does the same towards the grandpa-backed one
+		let mut tm_ctx = tm_client_context(&tm_client_id, Duration::from_secs(3));
+		conn_open_init(&mut tm_ctx, &tm_client_id, &grandpa_client_id);
+
+		for (ctx, client_id, counterparty_client_id) in [
+			(&grandpa_ctx, &grandpa_client_id, &tm_client_id),
+			(&tm_ctx, &tm_client_id, &grandpa_client_id),
+		] {
+			let connection_end = ctx.connection_end(&ConnectionId::new(0)).unwrap();
+			assert_eq!(connection_end.state(), &ConnectionState::Init);
+			assert_eq!(connection_end.client_id(), client_id);
+			assert_eq!(connection_end.counterparty().client_id(), counterparty_client_id);
+		}
+	}
+
+	#[test]
+	fn test_beefy_client_state_round_trips_through_any_client_state() {
+		let client_state = BeefyClientState::<Crypto> {
+			chain_id: ChainId::new(RelayChain::Rococo.to_string(), 2000),
+			relay_chain: RelayChain::Rococo,
+			para_id: 2000,
+			latest_para_height: 10,
+			latest_beefy_height: 20,
+			..Default::default()
+		};
+		let any_client_state = AnyClientState::Beefy(client_state.clone());
+		assert_eq!(any_client_state.client_type(), BeefyClientState::<Crypto>::client_type());
+		assert_eq!(any_client_state.latest_height(), Height::new(2000, 10));
+
+		let any = Any::from(any_client_state.clone());
+		assert_eq!(any.type_url, BEEFY_CLIENT_STATE_TYPE_URL);
+		assert_eq!(AnyClientState::try_from(any).unwrap(), any_client_state);
+
+		let decoded = AnyClientState::decode_vec(&any_client_state.encode_vec().unwrap()).unwrap();
+		match decoded {
+			AnyClientState::Beefy(decoded) => assert_eq!(decoded, client_state),
+			state => panic!("expected a beefy client state, got {state:?}"),
+		}
+	}
+
+	#[test]
+	fn test_grandpa_client_state_round_trips_through_any_client_state() {
+		let client_state = GrandpaClientState::<Crypto> {
+			relay_chain: RelayChain::Rococo,
+			latest_relay_height: 100,
+			latest_relay_hash: H256::repeat_byte(1),
+			frozen_height: Some(Height::new(2000, 8)),
+			latest_para_height: 10,
+			para_id: 2000,
+			current_set_id: 3,
+			current_authorities: (1..=3)
+				.map(|seed| (ed25519::Pair::from_seed(&[seed; 32]).public().into(), 1))
+				.collect(),
+			commitment_prefix: b"ibc/".to_vec(),
+			..Default::default()
+		};
+		let any_client_state = AnyClientState::Grandpa(client_state.clone());
+		assert_eq!(any_client_state.client_type(), GrandpaClientState::<Crypto>::client_type());
+		assert_eq!(any_client_state.latest_height(), Height::new(2000, 10));
+		assert_eq!(any_client_state.frozen_height(), Some(Height::new(2000, 8)));
+
+		let any = Any::from(any_client_state.clone());
+		assert_eq!(any.type_url, GRANDPA_CLIENT_STATE_TYPE_URL);
+		assert_eq!(AnyClientState::try_from(any).unwrap(), any_client_state);
+
+		let decoded = AnyClientState::decode_vec(&any_client_state.encode_vec().unwrap()).unwrap();
+		match decoded {
+			AnyClientState::Grandpa(decoded) => assert_eq!(decoded, client_state),
+			state => panic!("expected a grandpa client state, got {state:?}"),
+		}
+	}
+
+	#[test]
+	fn test_tm_update_with_another_host_functions_type() {
+		let client_id = ClientId::new(&ClientState::<()>::client_type(), 0).unwrap();
+		let ctx = MockContext::<MockClientTypes<SubstrateCrypto>>::new(
+			ChainId::new("mockgaiaA".to_string(), 1),
+			MockHostType::Mock,
+			5,
+			Height::new(1, 5),
+		);
+		let trusted_block = MockHostBlock::generate_tm_block(
+			ChainId::new("mockgaiaB".to_string(), 1),
+			10,
+			(ctx.host_timestamp() - Duration::from_secs(60)).unwrap(),
+		);
+		let ctx = with_tendermint_client(ctx, &client_id, trusted_block, Duration::from_secs(3));
+
+		let light_block = MockHostBlock::generate_tm_block(
+			ChainId::new("mockgaiaB".to_string(), 1),
+			11,
+			ctx.host_timestamp(),
+		);
+		let header = Header { trusted_height: Height::new(1, 10), ..light_block.into() };
+		let msg = MsgUpdateAnyClient::new(
+			client_id,
+			AnyClientMessage::Tendermint(ClientMessage::Header(header)),
+			get_dummy_account_id(),
+		);
+		let output = dispatch(&ctx, ClientMsg::UpdateClient(msg)).unwrap();
+		match output.result {
+			ClientResult::Update(result) => match result.client_state {
+				AnyClientState::Tendermint(client_state) =>
+					assert_eq!(client_state.latest_height(), Height::new(1, 11)),
+				_ => panic!("expected a tendermint client state"),
+			},
+			_ => panic!("expected result of type ClientResult::Update"),
+		}
+	}
 }
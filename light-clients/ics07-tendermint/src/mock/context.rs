@@ -14,10 +14,18 @@
 
 use crate::{
 	client_state::{test_util::get_dummy_tendermint_client_state, ClientState},
-	mock::{host::MockHostBlock, AnyConsensusState, MockClientTypes},
+	mock::{
+		host::MockHostBlock, AnyClientState, AnyConsensusState, MockClientTypes, MockHostFunctions,
+	},
 };
 use ibc::{
-	core::{ics02_client::client_state::ClientType, ics24_host::identifier::ClientId},
+	core::{
+		ics02_client::{
+			client_state::ClientType,
+			context::{ClientKeeper, ClientReader},
+		},
+		ics24_host::identifier::{ChainId, ClientId},
+	},
 	mock::{
 		client_state::{MockClientRecord, MockClientState, MockConsensusState},
 		context::MockContext,
@@ -27,7 +35,8 @@ use ibc::{
 	Height,
 };
 use log::debug;
-use std::ops::Sub;
+use std::{ops::Sub, time::Duration};
+use tendermint_testgen::light_block::TmLightBlock;
 
 /// Similar to `with_client`, this function associates a client record to this context, but
 /// additionally permits to parametrize two details of the client. If `client_type` is None,
@@ -148,3 +157,30 @@ pub fn with_client_parametrized_history(
 	ctx.ibc_store.lock().unwrap().clients.insert(client_id.clone(), client_record);
 	ctx
 }
+
+/// Associates a tendermint client to this context, trusting `light_block` as its latest
+/// consensus state and tolerating `max_clock_drift` between the counterparty and host clocks.
+/// The client is recorded as updated at the current host height and timestamp.
+pub fn with_tendermint_client<H: MockHostFunctions>(
+	mut ctx: MockContext<MockClientTypes<H>>,
+	client_id: &ClientId,
+	light_block: TmLightBlock,
+	max_clock_drift: Duration,
+) -> MockContext<MockClientTypes<H>> {
+	let header = light_block.signed_header.header.clone();
+	let height =
+		Height::new(ChainId::chain_version(header.chain_id.as_str()), header.height.value());
+	let client_state = match get_dummy_tendermint_client_state(header) {
+		AnyClientState::Tendermint(client_state) =>
+			AnyClientState::Tendermint(ClientState { max_clock_drift, ..client_state }),
+		_ => unreachable!(),
+	};
+	let consensus_state = AnyConsensusState::from(light_block);
+
+	let (host_height, host_timestamp) = (ctx.host_height(), ctx.host_timestamp());
+	ctx.store_client_state(client_id.clone(), client_state).unwrap();
+	ctx.store_consensus_state(client_id.clone(), height, consensus_state).unwrap();
+	ctx.store_update_height(client_id.clone(), height, host_height).unwrap();
+	ctx.store_update_time(client_id.clone(), height, host_timestamp).unwrap();
+	ctx
+}
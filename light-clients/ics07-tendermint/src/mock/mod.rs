@@ -57,30 +57,82 @@ use ibc::{
 };
 use ibc_derive::{ClientDef, ClientMessage, ClientState, ConsensusState};
 use ibc_proto::google::protobuf::Any;
+use ics10_grandpa::{
+	client_def::GrandpaClient,
+	client_message::{
+		ClientMessage as GrandpaClientMessage, RelayChainHeader, GRANDPA_CLIENT_MESSAGE_TYPE_URL,
+	},
+	client_state::{
+		ClientState as GrandpaClientState, UpgradeOptions as GrandpaUpgradeOptions,
+		GRANDPA_CLIENT_STATE_TYPE_URL,
+	},
+	consensus_state::{ConsensusState as GrandpaConsensusState, GRANDPA_CONSENSUS_STATE_TYPE_URL},
+};
+use ics11_beefy::{
+	client_def::BeefyClient,
+	client_message::{ClientMessage as BeefyClientMessage, BEEFY_CLIENT_MESSAGE_TYPE_URL},
+	client_state::{
+		ClientState as BeefyClientState, UpgradeOptions as BeefyUpgradeOptions,
+		BEEFY_CLIENT_STATE_TYPE_URL,
+	},
+	consensus_state::{ConsensusState as BeefyConsensusState, BEEFY_CONSENSUS_STATE_TYPE_URL},
+};
+use sp_core::{ed25519, Pair};
+use sp_runtime::traits::{BlakeTwo256, Header};
+use std::{cell::RefCell, collections::BTreeSet, marker::PhantomData};
 use tendermint_proto::Protobuf;
 
 pub const MOCK_CLIENT_STATE_TYPE_URL: &str = "/ibc.mock.ClientState";
 pub const MOCK_CLIENT_MESSAGE_TYPE_URL: &str = "/ibc.mock.ClientMessage";
 pub const MOCK_CONSENSUS_STATE_TYPE_URL: &str = "/ibc.mock.ConsensusState";
 
+/// Host functions required by every light client of [`AnyClient`].
+pub trait MockHostFunctions:
+	HostFunctionsProvider
+	+ grandpa_client_primitives::HostFunctions<Header = RelayChainHeader>
+	+ beefy_light_client_primitives::HostFunctions
+{
+}
+
+impl<H> MockHostFunctions for H where
+	H: HostFunctionsProvider
+		+ grandpa_client_primitives::HostFunctions<Header = RelayChainHeader>
+		+ beefy_light_client_primitives::HostFunctions
+{
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, ClientDef)]
-pub enum AnyClient {
+pub enum AnyClient<H>
+where
+	H: MockHostFunctions,
+{
 	Mock(MockClient),
-	Tendermint(TendermintClient<Crypto>),
+	Tendermint(TendermintClient<H>),
+	Grandpa(GrandpaClient<H>),
+	Beefy(BeefyClient<H>),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum AnyUpgradeOptions {
 	Mock(()),
 	Tendermint(TendermintUpgradeOptions),
+	Grandpa(GrandpaUpgradeOptions),
+	Beefy(BeefyUpgradeOptions),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, ClientState, Protobuf)]
-pub enum AnyClientState {
+pub enum AnyClientState<H>
+where
+	H: MockHostFunctions,
+{
 	#[ibc(proto_url = "MOCK_CLIENT_STATE_TYPE_URL")]
 	Mock(MockClientState),
 	#[ibc(proto_url = "TENDERMINT_CLIENT_STATE_TYPE_URL")]
-	Tendermint(TendermintClientState<Crypto>),
+	Tendermint(TendermintClientState<H>),
+	#[ibc(proto_url = "GRANDPA_CLIENT_STATE_TYPE_URL")]
+	Grandpa(GrandpaClientState<H>),
+	#[ibc(proto_url = "BEEFY_CLIENT_STATE_TYPE_URL")]
+	Beefy(BeefyClientState<H>),
 }
 #[derive(Clone, Debug, PartialEq, Eq, ClientMessage)]
 #[allow(clippy::large_enum_variant)]
@@ -89,6 +141,10 @@ pub enum AnyClientMessage {
 	Mock(MockClientMessage),
 	#[ibc(proto_url = "TENDERMINT_CLIENT_MESSAGE_TYPE_URL")]
 	Tendermint(ClientMessage),
+	#[ibc(proto_url = "GRANDPA_CLIENT_MESSAGE_TYPE_URL")]
+	Grandpa(GrandpaClientMessage),
+	#[ibc(proto_url = "BEEFY_CLIENT_MESSAGE_TYPE_URL")]
+	Beefy(BeefyClientMessage),
 }
 
 impl Protobuf<Any> for AnyClientMessage {}
@@ -104,6 +160,14 @@ impl TryFrom<Any> for AnyClientMessage {
 				ClientMessage::decode_vec(&value.value)
 					.map_err(ics02_client::error::Error::decode_raw_header)?,
 			)),
+			GRANDPA_CLIENT_MESSAGE_TYPE_URL => Ok(Self::Grandpa(
+				GrandpaClientMessage::decode_vec(&value.value)
+					.map_err(ics02_client::error::Error::decode_raw_header)?,
+			)),
+			BEEFY_CLIENT_MESSAGE_TYPE_URL => Ok(Self::Beefy(
+				BeefyClientMessage::decode_vec(&value.value)
+					.map_err(ics02_client::error::Error::decode_raw_header)?,
+			)),
 			_ => Err(ics02_client::error::Error::unknown_consensus_state_type(value.type_url)),
 		}
 	}
@@ -119,6 +183,14 @@ impl From<AnyClientMessage> for Any {
 				type_url: TENDERMINT_CLIENT_MESSAGE_TYPE_URL.to_string(),
 				value: msg.encode_vec().unwrap(),
 			},
+			AnyClientMessage::Grandpa(msg) => Any {
+				type_url: GRANDPA_CLIENT_MESSAGE_TYPE_URL.to_string(),
+				value: msg.encode_vec().unwrap(),
+			},
+			AnyClientMessage::Beefy(msg) => Any {
+				type_url: BEEFY_CLIENT_MESSAGE_TYPE_URL.to_string(),
+				value: msg.encode_vec().unwrap(),
+			},
 		}
 	}
 }
@@ -129,6 +201,10 @@ pub enum AnyConsensusState {
 	Tendermint(TendermintConsensusState),
 	#[ibc(proto_url = "MOCK_CONSENSUS_STATE_TYPE_URL")]
 	Mock(MockConsensusState),
+	#[ibc(proto_url = "GRANDPA_CONSENSUS_STATE_TYPE_URL")]
+	Grandpa(GrandpaConsensusState),
+	#[ibc(proto_url = "BEEFY_CONSENSUS_STATE_TYPE_URL")]
+	Beefy(BeefyConsensusState),
 }
 
 impl From<MockConsensusState> for AnyConsensusState {
@@ -137,23 +213,24 @@ impl From<MockConsensusState> for AnyConsensusState {
 	}
 }
 
-impl From<MockClientState> for AnyClientState {
+impl<H: MockHostFunctions> From<MockClientState> for AnyClientState<H> {
 	fn from(mcs: MockClientState) -> Self {
 		Self::Mock(mcs)
 	}
 }
 
+/// Client types of the mock context, whose light clients run on the host functions `H`.
 #[derive(Debug, Eq, PartialEq, Clone, Default)]
-pub struct MockClientTypes;
+pub struct MockClientTypes<H = Crypto>(PhantomData<H>);
 
-impl ClientTypes for MockClientTypes {
+impl<H: MockHostFunctions> ClientTypes for MockClientTypes<H> {
 	type AnyClientMessage = AnyClientMessage;
-	type AnyClientState = AnyClientState;
+	type AnyClientState = AnyClientState<H>;
 	type AnyConsensusState = AnyConsensusState;
-	type ClientDef = AnyClient;
+	type ClientDef = AnyClient<H>;
 }
 
-impl HostBlockType for MockClientTypes {
+impl<H: MockHostFunctions> HostBlockType for MockClientTypes<H> {
 	type HostBlock = MockHostBlock;
 }
 
@@ -183,8 +260,8 @@ impl ics23::HostFunctionsProvider for Crypto {
 }
 
 impl Sha256 for Crypto {
-	fn digest(_data: impl AsRef<[u8]>) -> [u8; HASH_SIZE] {
-		unimplemented!()
+	fn digest(data: impl AsRef<[u8]>) -> [u8; HASH_SIZE] {
+		<Self as ics23::HostFunctionsProvider>::sha2_256(data.as_ref())
 	}
 }
 
@@ -203,11 +280,58 @@ impl MerkleHash for Crypto {
 }
 
 impl Verifier for Crypto {
-	fn verify(_pubkey: PublicKey, _msg: &[u8], _signature: &Signature) -> Result<(), Error> {
-		unimplemented!()
+	fn verify(pubkey: PublicKey, msg: &[u8], signature: &Signature) -> Result<(), Error> {
+		let pubkey = pubkey.ed25519().ok_or(Error::UnsupportedKeyType)?;
+		let pubkey = ed25519_consensus::VerificationKey::try_from(pubkey.as_bytes())
+			.map_err(|_| Error::MalformedPublicKey)?;
+		let signature = ed25519_consensus::Signature::try_from(signature.as_bytes())
+			.map_err(|_| Error::MalformedSignature)?;
+		pubkey.verify(&signature, msg).map_err(|_| Error::VerificationFailed)
 	}
 }
 
 impl CommitValidator for Crypto {}
 
 impl HostFunctionsProvider for Crypto {}
+
+impl light_client_common::HostFunctions for Crypto {
+	type BlakeTwo256 = sp_runtime::traits::BlakeTwo256;
+	type Keccak256 = sp_runtime::traits::Keccak256;
+	type Sha256 = light_client_common::Sha256Hasher;
+
+	fn secp256k1_ecdsa_recover_compressed(
+		msg: &[u8; 32],
+		signature: &[u8; 65],
+	) -> Result<[u8; 33], light_client_common::EcdsaRecoveryError> {
+		sp_core::ecdsa::Signature::from_raw(*signature)
+			.recover_prehashed(msg)
+			.map(|public| public.0)
+			.ok_or(light_client_common::EcdsaRecoveryError)
+	}
+}
+
+thread_local! {
+	static RELAY_HEADER_HASHES: RefCell<BTreeSet<sp_core::H256>> = RefCell::new(BTreeSet::new());
+}
+
+impl grandpa_client_primitives::HostFunctions for Crypto {
+	type Header = sp_runtime::generic::Header<u32, BlakeTwo256>;
+
+	fn ed25519_verify(sig: &ed25519::Signature, msg: &[u8], pub_key: &ed25519::Public) -> bool {
+		ed25519::Pair::verify(sig, msg, pub_key)
+	}
+
+	fn insert_relay_header_hashes(headers: &[<Self::Header as Header>::Hash]) {
+		RELAY_HEADER_HASHES.with(|set| set.borrow_mut().extend(headers.iter().copied()))
+	}
+
+	fn contains_relay_header_hash(hash: <Self::Header as Header>::Hash) -> bool {
+		RELAY_HEADER_HASHES.with(|set| set.borrow().contains(&hash))
+	}
+}
+
+impl beefy_light_client_primitives::HostFunctions for Crypto {
+	fn keccak_256(input: &[u8]) -> [u8; 32] {
+		sp_core::keccak_256(input)
+	}
+}
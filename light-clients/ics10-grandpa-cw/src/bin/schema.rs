@@ -1,10 +1,11 @@
-// use cosmwasm_schema::write_api;
-// use ics10_grandpa_cw::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use cosmwasm_schema::write_api;
+use ics10_grandpa_cw::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
-	// write_api! {
-	// 	instantiate: InstantiateMsg,
-	// 	execute: ExecuteMsg,
-	// 	query: QueryMsg,
-	// }
+	write_api! {
+		instantiate: InstantiateMsg,
+		execute: ExecuteMsg,
+		query: QueryMsg,
+		migrate: MigrateMsg,
+	}
 }
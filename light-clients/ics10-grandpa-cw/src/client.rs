@@ -15,7 +15,10 @@
 
 use crate::{
 	context::Context,
-	contract::{CLIENT_COUNTER, CONSENSUS_STATES_HEIGHTS, HOST_CONSENSUS_STATE},
+	contract::{
+		CLIENT_COUNTER, CLIENT_UPDATE_HEIGHT, CLIENT_UPDATE_TIME, CONSENSUS_STATES_HEIGHTS,
+		HOST_CONSENSUS_STATE,
+	},
 	ics23::{
 		ClientStates, ConsensusStates, FakeInner, ReadonlyClientStates, ReadonlyClients,
 		ReadonlyConsensusStates,
@@ -31,7 +34,7 @@ use ibc::{
 			context::{ClientKeeper, ClientReader, ClientTypes},
 			error::Error,
 		},
-		ics24_host::identifier::ClientId,
+		ics24_host::identifier::{ChainId, ClientId},
 	},
 	protobuf::Protobuf,
 	timestamp::Timestamp,
@@ -149,7 +152,7 @@ impl<'a, H: HostFunctions<Header = RelayChainHeader>> ClientReader for Context<'
 
 	fn host_height(&self) -> Height {
 		log!(self, "in client: [host_height]");
-		Height::new(self.env.block.height, 0)
+		Height::new(ChainId::chain_version(&self.env.block.chain_id), self.env.block.height)
 	}
 
 	fn host_timestamp(&self) -> Timestamp {
@@ -233,20 +236,34 @@ impl<'a, H: HostFunctions<Header = RelayChainHeader>> ClientKeeper for Context<'
 
 	fn store_update_time(
 		&mut self,
-		_client_id: ClientId,
-		_height: Height,
-		_timestamp: Timestamp,
+		client_id: ClientId,
+		height: Height,
+		timestamp: Timestamp,
 	) -> Result<(), Error> {
-		unimplemented!()
+		let key = (client_id.as_bytes().to_owned(), height.to_string().into_bytes());
+		CLIENT_UPDATE_TIME
+			.save(self.storage_mut(), key, &timestamp.nanoseconds())
+			.map_err(|e| {
+				Error::implementation_specific(format!(
+					"[store_update_time]: error saving time {e}"
+				))
+			})
 	}
 
 	fn store_update_height(
 		&mut self,
-		_client_id: ClientId,
-		_height: Height,
-		_host_height: Height,
+		client_id: ClientId,
+		height: Height,
+		host_height: Height,
 	) -> Result<(), Error> {
-		unimplemented!()
+		let key = (client_id.as_bytes().to_owned(), height.to_string().into_bytes());
+		CLIENT_UPDATE_HEIGHT
+			.save(self.storage_mut(), key, &host_height.to_string().into_bytes())
+			.map_err(|e| {
+				Error::implementation_specific(format!(
+					"[store_update_height]: error saving height {e}"
+				))
+			})
 	}
 
 	fn validate_self_client(&self, _client_state: &Self::AnyClientState) -> Result<(), Error> {
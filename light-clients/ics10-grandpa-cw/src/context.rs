@@ -15,20 +15,23 @@
 
 use crate::{
 	contract::{
-		GRANDPA_BLOCK_HASHES_CACHE_SIZE, GRANDPA_HEADER_HASHES_SET_STORAGE,
-		GRANDPA_HEADER_HASHES_STORAGE,
+		CLIENT_UPDATE_HEIGHT, CLIENT_UPDATE_TIME, GRANDPA_BLOCK_HASHES_CACHE_SIZE,
+		GRANDPA_HEADER_HASHES_SET_STORAGE, GRANDPA_HEADER_HASHES_STORAGE,
 	},
 	ics23::{ClientStates, ConsensusStates, ReadonlyClientStates, ReadonlyConsensusStates},
 	ContractError,
 };
 use cosmwasm_std::{DepsMut, Env, Storage};
 use grandpa_light_client_primitives::HostFunctions;
-use ibc::{core::ics26_routing::context::ReaderContext, Height};
+use ibc::{
+	core::{ics24_host::identifier::ClientId, ics26_routing::context::ReaderContext},
+	Height,
+};
 use ics10_grandpa::{
 	client_message::RelayChainHeader, client_state::ClientState, consensus_state::ConsensusState,
 };
 use sp_core::H256;
-use std::{fmt, fmt::Debug, marker::PhantomData};
+use std::{fmt, fmt::Debug, marker::PhantomData, str::FromStr};
 
 pub struct Context<'a, H> {
 	pub deps: DepsMut<'a>,
@@ -105,6 +108,34 @@ impl<'a, H> Context<'a, H> {
 			.unwrap_or_default()
 			.contains(&hash)
 	}
+
+	/// Returns the host timestamp (in nanoseconds) at which the consensus state at `height` was
+	/// stored.
+	pub fn processed_timestamp(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<u64, ContractError> {
+		let key = (client_id.as_bytes().to_owned(), height.to_string().into_bytes());
+		CLIENT_UPDATE_TIME.load(self.storage(), key).map_err(|_| {
+			ContractError::Grandpa(format!("processed time not found for height {height}"))
+		})
+	}
+
+	/// Returns the host height at which the consensus state at `height` was stored.
+	pub fn processed_height(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Height, ContractError> {
+		let key = (client_id.as_bytes().to_owned(), height.to_string().into_bytes());
+		let bytes = CLIENT_UPDATE_HEIGHT.load(self.storage(), key).map_err(|_| {
+			ContractError::Grandpa(format!("processed height not found for height {height}"))
+		})?;
+		let processed_height = String::from_utf8(bytes)?;
+		Height::from_str(&processed_height)
+			.map_err(|e| ContractError::Grandpa(format!("invalid processed height: {e}")))
+	}
 }
 
 impl<'a, H> Context<'a, H>
@@ -162,6 +193,31 @@ where
 		client_states.insert_prefixed(encoded, prefix);
 		Ok(())
 	}
+
+	/// Copies the processed time and height of the consensus state at `height` from the client
+	/// stored under the `from` prefix to the client stored under the `to` prefix.
+	pub fn copy_processed_metadata_prefixed(
+		&mut self,
+		client_id: &ClientId,
+		height: Height,
+		from: &[u8],
+		to: &[u8],
+	) -> Result<(), ContractError> {
+		let key = (client_id.as_bytes().to_owned(), height.to_string().into_bytes());
+		let keys = [
+			("time", CLIENT_UPDATE_TIME.key(key.clone()).to_vec()),
+			("height", CLIENT_UPDATE_HEIGHT.key(key).to_vec()),
+		];
+		for (name, key) in keys {
+			let value = self.storage().get(&[from, key.as_slice()].concat()).ok_or_else(|| {
+				ContractError::Grandpa(format!(
+					"processed {name} not found for height {height} and prefix {from:?}"
+				))
+			})?;
+			self.storage_mut().set(&[to, key.as_slice()].concat(), &value);
+		}
+		Ok(())
+	}
 }
 
 impl<'a, H: HostFunctions<Header = RelayChainHeader>> ReaderContext for Context<'a, H> {}
@@ -16,31 +16,47 @@
 use crate::{
 	context::Context,
 	error::ContractError,
+	helpers::{
+		migrate_from_legacy_layout, prune_excess_consensus_states, prune_expired_consensus_states,
+		set_max_consensus_states, validate_initial_states, verify_delay_passed,
+	},
 	log,
 	msg::{
-		CheckForMisbehaviourMsg, CheckSubstituteAndUpdateStateMsg, ContractResult, ExecuteMsg,
-		ExportMetadataMsg, InstantiateMsg, QueryMsg, QueryResponse, StatusMsg, UpdateStateMsg,
-		UpdateStateOnMisbehaviourMsg, VerifyClientMessage, VerifyMembershipMsg, MigrateMsg,
-		VerifyNonMembershipMsg, VerifyUpgradeAndUpdateStateMsg
+		CheckForMisbehaviourMsg, CheckSubstituteAndUpdateStateMsg, ClientTypeMsg, ContractResult,
+		ExecuteMsg, ExportMetadataMsg, GetLatestHeightsMsg, InstantiateMsg,
+		IterateConsensusStatesMsg, MigrateMsg, QueryMsg, QueryResponse, StatusMsg,
+		TimestampAtHeightMsg, UpdateStateMsg, UpdateStateOnMisbehaviourMsg, VerifyClientMessage,
+		VerifyMembershipMsg, VerifyNonMembershipMsg, VerifyUpgradeAndUpdateStateMsg,
+	},
+	state::{
+		get_client_state, get_consensus_state, get_consensus_states_page, get_genesis_metadata,
+		get_latest_heights,
 	},
-	state::{get_client_state, get_consensus_state},
+	types::ContractVersion,
 	Bytes,
 };
 use byteorder::{ByteOrder, LittleEndian};
 use core::hash::Hasher;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+	to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage,
+};
 use cw_storage_plus::{Item, Map};
 use digest::Digest;
 use grandpa_light_client_primitives::justification::AncestryChain;
-use ibc::core::{
-	ics02_client::{
-		client_def::{ClientDef, ConsensusUpdateResult},
-		context::{ClientKeeper, ClientReader},
-		height::Height,
+use ibc::{
+	core::{
+		ics02_client::{
+			client_consensus::ConsensusState as _,
+			client_def::{ClientDef, ConsensusUpdateResult},
+			context::{ClientKeeper, ClientReader},
+			height::Height,
+		},
+		ics23_commitment::commitment::CommitmentRoot,
+		ics24_host::identifier::ClientId,
 	},
-	ics24_host::identifier::ClientId,
+	timestamp::Timestamp,
 };
 use ics08_wasm::{SUBJECT_PREFIX, SUBSTITUTE_PREFIX};
 use ics10_grandpa::{
@@ -53,12 +69,17 @@ use light_client_common::{verify_membership, verify_non_membership};
 use sp_core::H256;
 use sp_runtime::traits::{BlakeTwo256, Header};
 use sp_runtime_interface::unpack_ptr_and_len;
-use std::{collections::BTreeSet, str::FromStr};
-/*
+use std::{
+	collections::{btree_map::Entry, BTreeMap, BTreeSet},
+	str::FromStr,
+};
 // version info for migration info
-const CONTRACT_NAME: &str = "crates.io:ics10-grandpa-cw";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
-*/
+pub const CONTRACT_NAME: &str = "crates.io:ics10-grandpa-cw";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The first released version, which didn't store its version.
+pub const LEGACY_CONTRACT_VERSION: &str = "0.1.0";
+
+pub const CONTRACT_INFO: Item<ContractVersion> = Item::new("contract_info");
 
 pub const CHANNELS_CONNECTION: Map<Bytes, Vec<(Bytes, Bytes)>> = Map::new("channels_connection");
 pub const CLIENT_UPDATE_TIME: Map<(Bytes, Bytes), u64> = Map::new("client_update_time");
@@ -82,6 +103,16 @@ pub struct HostFunctions;
 
 impl light_client_common::HostFunctions for HostFunctions {
 	type BlakeTwo256 = BlakeTwo256;
+	type Keccak256 = sp_runtime::traits::Keccak256;
+	type Sha256 = light_client_common::Sha256Hasher;
+
+	fn secp256k1_ecdsa_recover_compressed(
+		_msg: &[u8; 32],
+		_signature: &[u8; 65],
+	) -> Result<[u8; 33], light_client_common::EcdsaRecoveryError> {
+		// GRANDPA justifications are signed with ed25519, ECDSA recovery isn't needed here.
+		Err(light_client_common::EcdsaRecoveryError)
+	}
 }
 
 impl grandpa_light_client_primitives::HostFunctions for HostFunctions {
@@ -114,21 +145,57 @@ impl grandpa_light_client_primitives::HostFunctions for HostFunctions {
 }
 
 #[entry_point]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    // No state migrations performed, just returned a Response
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+	let MigrateMsg::Migrate { from_version } = msg;
+	if let Some(stored) = CONTRACT_INFO.may_load(deps.storage)? {
+		if stored.contract != CONTRACT_NAME || stored.version != from_version {
+			return Err(ContractError::InvalidMigration(format!(
+				"stored contract {} {} doesn't match the version {from_version} to migrate from",
+				stored.contract, stored.version
+			)))
+		}
+	}
+	let mut ctx = Context::<HostFunctions>::new(deps, env);
+	match from_version.as_str() {
+		LEGACY_CONTRACT_VERSION => migrate_from_legacy_layout(&mut ctx)?,
+		CONTRACT_VERSION => {},
+		_ =>
+			return Err(ContractError::InvalidMigration(format!(
+				"unknown contract version {from_version}"
+			))),
+	}
+	set_contract_version(ctx.storage_mut())?;
+	Ok(Response::default())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-	_deps: DepsMut,
+	deps: DepsMut,
 	_env: Env,
 	_info: MessageInfo,
-	_msg: InstantiateMsg,
+	msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+	validate_initial_states::<HostFunctions>(deps.storage)?;
+	if let Some(max_consensus_states) = msg.max_consensus_states {
+		if max_consensus_states == 0 {
+			return Err(ContractError::InvalidInstantiateMsg(
+				"max_consensus_states must be at least 1".to_string(),
+			))
+		}
+		set_max_consensus_states::<HostFunctions>(deps.storage, max_consensus_states)?;
+	}
+	set_contract_version(deps.storage)?;
 	Ok(Response::default())
 }
 
+fn set_contract_version(storage: &mut dyn Storage) -> StdResult<()> {
+	let version = ContractVersion {
+		contract: CONTRACT_NAME.to_string(),
+		version: CONTRACT_VERSION.to_string(),
+	};
+	CONTRACT_INFO.save(storage, &version)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
 	deps: DepsMut,
@@ -155,33 +222,59 @@ fn process_message(
 	let result = match msg {
 		ExecuteMsg::VerifyMembership(msg) => {
 			let msg = VerifyMembershipMsg::try_from(msg)?;
-			let consensus_state = ctx
-				.consensus_state(&client_id, msg.height)
+			let client_state = ctx
+				.client_state(&client_id)
 				.map_err(|e| ContractError::Grandpa(e.to_string()))?;
-			verify_membership::<BlakeTwo256, _>(
-				&msg.prefix,
-				&msg.proof,
-				&consensus_state.root,
-				msg.path,
-				msg.value,
-			)
-			.map_err(|e| ContractError::Grandpa(e.to_string()))?;
-			Ok(()).map(|_| to_binary(&ContractResult::success()))
+			verify_membership_msg(ctx, &client_id, &client_state, &mut BTreeMap::new(), msg)
+				.map(|_| to_binary(&ContractResult::success()))
 		},
 		ExecuteMsg::VerifyNonMembership(msg) => {
 			let msg = VerifyNonMembershipMsg::try_from(msg)?;
-			let consensus_state = ctx
-				.consensus_state(&client_id, msg.height)
+			let client_state = ctx
+				.client_state(&client_id)
 				.map_err(|e| ContractError::Grandpa(e.to_string()))?;
-
-			verify_non_membership::<BlakeTwo256, _>(
-				&msg.prefix,
-				&msg.proof,
-				&consensus_state.root,
-				msg.path,
-			)
-			.map_err(|e| ContractError::Grandpa(e.to_string()))
-			.map(|_| to_binary(&ContractResult::success()))
+			verify_non_membership_msg(ctx, &client_id, &client_state, &mut BTreeMap::new(), msg)
+				.map(|_| to_binary(&ContractResult::success()))
+		},
+		ExecuteMsg::VerifyMembershipBatch(msgs) => {
+			let client_state = ctx
+				.client_state(&client_id)
+				.map_err(|e| ContractError::Grandpa(e.to_string()))?;
+			let mut consensus_states = BTreeMap::new();
+			let results = msgs
+				.into_iter()
+				.map(|msg| {
+					let msg = VerifyMembershipMsg::try_from(msg)?;
+					verify_membership_msg(
+						ctx,
+						&client_id,
+						&client_state,
+						&mut consensus_states,
+						msg,
+					)
+				})
+				.collect();
+			Ok(to_binary(&ContractResult::batch(results)))
+		},
+		ExecuteMsg::VerifyNonMembershipBatch(msgs) => {
+			let client_state = ctx
+				.client_state(&client_id)
+				.map_err(|e| ContractError::Grandpa(e.to_string()))?;
+			let mut consensus_states = BTreeMap::new();
+			let results = msgs
+				.into_iter()
+				.map(|msg| {
+					let msg = VerifyNonMembershipMsg::try_from(msg)?;
+					verify_non_membership_msg(
+						ctx,
+						&client_id,
+						&client_state,
+						&mut consensus_states,
+						msg,
+					)
+				})
+				.collect();
+			Ok(to_binary(&ContractResult::batch(results)))
 		},
 		ExecuteMsg::VerifyClientMessage(msg) => {
 			let client_state = ctx
@@ -237,6 +330,7 @@ fn process_message(
 				.client_state(&client_id)
 				.map_err(|e| ContractError::Grandpa(e.to_string()))?;
 			let msg = UpdateStateMsg::try_from(msg_raw)?;
+			prune_expired_consensus_states(ctx, &client_id, &client_state)?;
 
 			let finalized_headers = match &msg.client_message {
 				ClientMessage::Header(header) => {
@@ -260,7 +354,18 @@ fn process_message(
 				.map_err(|e| ContractError::Grandpa(e.to_string()))
 				.and_then(|(cs, cu)| {
 					ctx.insert_relay_header_hashes(&finalized_headers);
-					store_client_and_consensus_states(ctx, client_id.clone(), cs, cu)
+					let (latest_height, max_consensus_states) =
+						(cs.latest_height(), cs.max_consensus_states);
+					let result = store_client_and_consensus_states(ctx, client_id.clone(), cs, cu)?;
+					if let Some(max_consensus_states) = max_consensus_states {
+						prune_excess_consensus_states(
+							ctx,
+							&client_id,
+							latest_height,
+							max_consensus_states,
+						);
+					}
+					Ok(result)
 				})
 		},
 		ExecuteMsg::CheckSubstituteAndUpdateState(msg) => {
@@ -273,23 +378,54 @@ fn process_message(
 				.client_state_prefixed(SUBSTITUTE_PREFIX)
 				.map_err(|e| ContractError::Grandpa(e.to_string()))?;
 
+			// The substitute client must be active, i.e. neither frozen nor expired.
+			if substitute_client_state.frozen_height.is_some() {
+				return Err(ContractError::Grandpa("substitute client is frozen".to_string()))
+			}
+			let height = substitute_client_state.latest_height();
+			let substitute_consensus_state =
+				ctx.consensus_state_prefixed(height, SUBSTITUTE_PREFIX)?;
+			let elapsed = ctx
+				.host_timestamp()
+				.duration_since(&substitute_consensus_state.timestamp())
+				.unwrap_or_default();
+			if substitute_client_state.expired(elapsed) {
+				return Err(ContractError::Grandpa("substitute client is expired".to_string()))
+			}
+
+			// The substitute must track the same chain and must not be behind the subject.
+			if substitute_client_state.chain_id() != old_client_state.chain_id() {
+				return Err(ContractError::Grandpa(format!(
+					"substitute client chain id {} does not match subject client chain id {}",
+					substitute_client_state.chain_id(),
+					old_client_state.chain_id()
+				)))
+			}
+			if height < old_client_state.latest_height() {
+				return Err(ContractError::Grandpa(format!(
+					"substitute client height {height} is lower than subject client height {}",
+					old_client_state.latest_height()
+				)))
+			}
+
 			// Check that the substitute client state is valid:
-			// all fields should be the same as in the old state, except for the `relay_chain`,
-			// `para_id`, `latest_para_height`, `latest_relay_height`, `latest_relay_hash`,
-			// `frozen_height`, `current_authorities`, `current_set_id`
+			// all fields should be the same as in the old state, except for the
+			// `latest_para_height`, `latest_relay_height`, `latest_relay_hash`, `frozen_height`,
+			// `current_authorities`, `current_set_id`
 			let ClientState {
-				relay_chain,
+				relay_chain: _,
 				latest_relay_height,
 				latest_relay_hash,
 				frozen_height,
 				latest_para_height,
-				para_id,
+				para_id: _,
 				current_set_id,
 				current_authorities,
+				commitment_prefix: _,
+				custom_trusting_period: _,
+				max_consensus_states: _,
 				_phantom,
 			} = substitute_client_state.clone();
-			old_client_state.relay_chain = relay_chain;
-			old_client_state.para_id = para_id;
 			old_client_state.latest_para_height = latest_para_height;
 			old_client_state.latest_relay_height = latest_relay_height;
 			old_client_state.latest_relay_hash = latest_relay_hash;
@@ -297,17 +433,46 @@ fn process_message(
 			old_client_state.current_authorities = current_authorities.clone();
 			old_client_state.current_set_id = current_set_id;
 
-			if old_client_state != substitute_client_state {
-				return Err(ContractError::Grandpa(
-					"subject client state does not match substitute client state".to_string(),
-				))
+			let mismatched_fields = [
+				(
+					"relay_chain",
+					old_client_state.relay_chain != substitute_client_state.relay_chain,
+				),
+				("para_id", old_client_state.para_id != substitute_client_state.para_id),
+				(
+					"commitment_prefix",
+					old_client_state.commitment_prefix != substitute_client_state.commitment_prefix,
+				),
+				(
+					"custom_trusting_period",
+					old_client_state.custom_trusting_period !=
+						substitute_client_state.custom_trusting_period,
+				),
+				(
+					"max_consensus_states",
+					old_client_state.max_consensus_states !=
+						substitute_client_state.max_consensus_states,
+				),
+			]
+			.into_iter()
+			.filter_map(|(field, mismatched)| mismatched.then_some(field))
+			.collect::<Vec<_>>();
+			if !mismatched_fields.is_empty() || old_client_state != substitute_client_state {
+				return Err(ContractError::Grandpa(format!(
+					"subject client state does not match substitute client state in fields: \
+					 {mismatched_fields:?}"
+				)))
 			}
 			let substitute_client_state = old_client_state;
-			let height = substitute_client_state.latest_height();
-			// consensus state should be replaced as well
-			let substitute_consensus_state =
-				ctx.consensus_state_prefixed(height, SUBSTITUTE_PREFIX)?;
+			// consensus state should be replaced as well, along with the time and height at which
+			// it was processed, which are needed to verify the connection delay
 			ctx.store_consensus_state_prefixed(height, substitute_consensus_state, SUBJECT_PREFIX);
+			ctx.copy_processed_metadata_prefixed(
+				&client_id,
+				height,
+				SUBSTITUTE_PREFIX,
+				SUBJECT_PREFIX,
+			)?;
 			ctx.store_client_state_prefixed(substitute_client_state, SUBJECT_PREFIX)
 				.map_err(|e| ContractError::Grandpa(e.to_string()))?;
 
@@ -338,33 +503,144 @@ fn process_message(
 	Ok(result??)
 }
 
+/// Checks the delay period of a proof at `height` and returns the commitment root it's verified
+/// against. Consensus states are looked up in `consensus_states` first, so that the items of a
+/// batch load each of them only once.
+fn proof_root(
+	ctx: &Context<HostFunctions>,
+	client_id: &ClientId,
+	consensus_states: &mut BTreeMap<Height, ConsensusState>,
+	height: Height,
+	delay_time_period: u64,
+	delay_block_period: u64,
+) -> Result<CommitmentRoot, ContractError> {
+	// The proof must be checked against the root of a consensus state the client has stored at
+	// exactly the proof height.
+	let consensus_state = match consensus_states.entry(height) {
+		Entry::Occupied(entry) => entry.into_mut(),
+		Entry::Vacant(entry) => entry.insert(
+			ctx.maybe_consensus_state(client_id, height)
+				.map_err(|e| ContractError::Grandpa(e.to_string()))?
+				.ok_or_else(|| {
+					ContractError::InvalidHeight(format!(
+						"no consensus state found at proof height {height}"
+					))
+				})?,
+		),
+	};
+	let root = consensus_state.root.clone();
+	verify_delay_passed(ctx, client_id, height, delay_time_period, delay_block_period)?;
+	Ok(root)
+}
+
+fn verify_membership_msg(
+	ctx: &Context<HostFunctions>,
+	client_id: &ClientId,
+	client_state: &ClientState<HostFunctions>,
+	consensus_states: &mut BTreeMap<Height, ConsensusState>,
+	msg: VerifyMembershipMsg,
+) -> Result<(), ContractError> {
+	client_state.verify_prefix(&msg.prefix)?;
+	let root = proof_root(
+		ctx,
+		client_id,
+		consensus_states,
+		msg.height,
+		msg.delay_time_period,
+		msg.delay_block_period,
+	)?;
+	verify_membership::<BlakeTwo256, _>(&msg.prefix, &msg.proof, &root, msg.path, msg.value)
+		.map_err(|e| ContractError::Grandpa(e.to_string()))
+}
+
+fn verify_non_membership_msg(
+	ctx: &Context<HostFunctions>,
+	client_id: &ClientId,
+	client_state: &ClientState<HostFunctions>,
+	consensus_states: &mut BTreeMap<Height, ConsensusState>,
+	msg: VerifyNonMembershipMsg,
+) -> Result<(), ContractError> {
+	client_state.verify_prefix(&msg.prefix)?;
+	let root = proof_root(
+		ctx,
+		client_id,
+		consensus_states,
+		msg.height,
+		msg.delay_time_period,
+		msg.delay_block_period,
+	)?;
+	verify_non_membership::<BlakeTwo256, _>(&msg.prefix, &msg.proof, &root, msg.path)
+		.map_err(|e| ContractError::Grandpa(e.to_string()))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
 	let client_id = ClientId::from_str("08-wasm-0").expect("client id is valid");
 	match msg {
-		QueryMsg::ClientTypeMsg(_) => unimplemented!("ClientTypeMsg"),
-		QueryMsg::GetLatestHeightsMsg(_) => unimplemented!("GetLatestHeightsMsg"),
-		QueryMsg::ExportMetadata(ExportMetadataMsg {}) =>
-			to_binary(&QueryResponse::genesis_metadata(None)),
+		QueryMsg::ClientTypeMsg(ClientTypeMsg {}) =>
+			to_binary(&QueryResponse::client_type(ClientState::<HostFunctions>::client_type())),
+		QueryMsg::GetLatestHeightsMsg(GetLatestHeightsMsg {}) => {
+			let heights =
+				get_latest_heights::<HostFunctions>(deps).into_iter().map(Into::into).collect();
+			to_binary(&QueryResponse::heights(heights))
+		},
+		QueryMsg::ExportMetadata(ExportMetadataMsg {}) => to_binary(
+			&QueryResponse::genesis_metadata(Some(get_genesis_metadata(deps, &client_id))),
+		),
 		QueryMsg::Status(StatusMsg {}) => {
-			let client_state = match get_client_state::<HostFunctions>(deps) {
-				Ok(client_state) => client_state,
-				Err(_) => return to_binary(&QueryResponse::status("Unknown".to_string())),
-			};
-
-			if client_state.frozen_height().is_some() {
-				to_binary(&QueryResponse::status("Frozen".to_string()))
-			} else {
-				let height = client_state.latest_height();
-				match get_consensus_state(deps, &client_id, height) {
-					Ok(_) => to_binary(&QueryResponse::status("Active".to_string())),
-					Err(_) => to_binary(&QueryResponse::status("Expired".to_string())),
-				}
-			}
+			let client_state = get_client_state::<HostFunctions>(deps)
+				.map_err(|e| StdError::generic_err(format!("contract is not initialized: {e}")))?;
+			let status = client_status(deps, &env, &client_id, &client_state);
+			let frozen_height = client_state.frozen_height().map(Into::into);
+			to_binary(&QueryResponse::status(status, frozen_height))
+		},
+		QueryMsg::TimestampAtHeight(TimestampAtHeightMsg { height }) => {
+			let height = Height::from(height);
+			let consensus_state = get_consensus_state(deps, &client_id, height)
+				.and_then(|value| Context::<HostFunctions>::decode_consensus_state(&value))
+				.map_err(|e| StdError::generic_err(e.to_string()))?;
+			to_binary(&QueryResponse::timestamp(consensus_state.timestamp().nanoseconds()))
+		},
+		QueryMsg::IterateConsensusStates(IterateConsensusStatesMsg { start_height, limit }) => {
+			let start_height = start_height.map(Height::from);
+			let consensus_states = get_consensus_states_page(deps, start_height, limit as usize);
+			to_binary(&QueryResponse::consensus_states(consensus_states))
 		},
 	}
 }
 
+/// Computes the status of the client as reported to the 08-wasm module: "Frozen" once a frozen
+/// height is set, "Expired" when the latest consensus state is missing or older than the trusting
+/// period, "Unknown" when the block time can't be compared against it, "Active" otherwise.
+fn client_status(
+	deps: Deps,
+	env: &Env,
+	client_id: &ClientId,
+	client_state: &ClientState<HostFunctions>,
+) -> String {
+	if client_state.frozen_height().is_some() {
+		return "Frozen".to_string()
+	}
+	let consensus_state = match get_consensus_state(deps, client_id, client_state.latest_height())
+		.and_then(|value| Context::<HostFunctions>::decode_consensus_state(&value))
+	{
+		Ok(consensus_state) => consensus_state,
+		Err(_) => return "Expired".to_string(),
+	};
+	let now = match Timestamp::from_nanoseconds(env.block.time.nanos()) {
+		Ok(now) if now != Timestamp::none() => now,
+		_ => return "Unknown".to_string(),
+	};
+	let expired = now
+		.duration_since(&consensus_state.timestamp())
+		.map_or(false, |elapsed| client_state.expired(elapsed));
+	if expired {
+		"Expired".to_string()
+	} else {
+		"Active".to_string()
+	}
+}
+
 fn store_client_and_consensus_states<H>(
 	ctx: &mut Context<H>,
 	client_id: ClientId,
@@ -375,23 +651,27 @@ where
 	H: grandpa_light_client_primitives::HostFunctions<Header = RelayChainHeader>,
 {
 	let height = client_state.latest_height();
-	match consensus_update {
-		ConsensusUpdateResult::Single(cs) => {
-			log!(ctx, "Storing consensus state: {:?}", height);
-			ctx.store_consensus_state(client_id.clone(), height, cs)
-				.map_err(|e| ContractError::Grandpa(e.to_string()))?;
-		},
-		ConsensusUpdateResult::Batch(css) =>
-			for (height, cs) in css {
-				log!(ctx, "Storing consensus state: {:?}", height);
-				ctx.store_consensus_state(client_id.clone(), height, cs)
-					.map_err(|e| ContractError::Grandpa(e.to_string()))?;
-			},
+	let host_timestamp = ctx.host_timestamp();
+	let host_height = ctx.host_height();
+	let consensus_states = match consensus_update {
+		ConsensusUpdateResult::Single(cs) => vec![(height, cs)],
+		ConsensusUpdateResult::Batch(css) => css,
+	};
+	let heights = consensus_states.iter().map(|(height, _)| (*height).into()).collect();
+	for (height, cs) in consensus_states {
+		log!(ctx, "Storing consensus state: {:?}", height);
+		ctx.store_consensus_state(client_id.clone(), height, cs)
+			.map_err(|e| ContractError::Grandpa(e.to_string()))?;
+		// processed time and height are used to check the connection delay period
+		ctx.store_update_time(client_id.clone(), height, host_timestamp)
+			.map_err(|e| ContractError::Grandpa(e.to_string()))?;
+		ctx.store_update_height(client_id.clone(), height, host_height)
+			.map_err(|e| ContractError::Grandpa(e.to_string()))?;
 	}
 	log!(ctx, "Storing client state with height: {:?}", height);
 	ctx.store_client_state(client_id, client_state)
 		.map_err(|e| ContractError::Grandpa(e.to_string()))?;
-	Ok(to_binary(&ContractResult::success()))
+	Ok(to_binary(&ContractResult::success().heights(heights)))
 }
 
 // The FFIs below are required because of sp-io dependency that expects the functions to be
@@ -439,3 +719,932 @@ pub extern "C" fn ext_hashing_twox_64_version_1(data: i64) -> i32 {
 	let out_ptr = Box::leak(hash).as_ptr();
 	out_ptr as i32
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		ics23::{ClientStates, FakeInner},
+		msg::{
+			CheckForMisbehaviourMsgRaw, CheckSubstituteAndUpdateStateMsgRaw, ClientMessageRaw,
+			GenesisMetadata, MerklePath, VerifyClientMessageRaw, VerifyMembershipMsgRaw,
+			VerifyNonMembershipMsgRaw, WasmMisbehaviour,
+		},
+	};
+	use codec::Encode;
+	use cosmwasm_std::{
+		from_binary,
+		testing::{mock_dependencies, mock_env, mock_info},
+	};
+	use grandpa_light_client_primitives::{justification::GrandpaJustification, FinalityProof};
+	use ibc::protobuf::Protobuf;
+	use ibc_proto::google::protobuf::Any;
+	use ics08_wasm::{
+		client_message::Header as WasmHeader, client_state::WASM_CLIENT_STATE_TYPE_URL,
+	};
+	use ics10_grandpa::client_message::{
+		Header as GrandpaHeader, Misbehaviour, GRANDPA_HEADER_TYPE_URL,
+		GRANDPA_MISBEHAVIOUR_TYPE_URL,
+	};
+	use prost::Message;
+	use sp_core::{
+		ed25519,
+		storage::{ChildInfo, StateVersion, Storage, StorageChild},
+		Pair,
+	};
+	use sp_state_machine::{prove_child_read, InMemoryBackend};
+	use std::time::Duration;
+
+	/// Encodes `client_state` wrapped in a wasm client state, the way 08-wasm stores it.
+	fn wasm_client_state(client_state: &ClientState<HostFunctions>) -> Vec<u8> {
+		wasm_client_state_of_any(client_state.to_any(), client_state.latest_height())
+	}
+
+	/// Encodes the client state `any` of any client type wrapped in a wasm client state.
+	fn wasm_client_state_of_any(any: Any, latest_height: Height) -> Vec<u8> {
+		let wasm_client_state =
+			ics08_wasm::client_state::ClientState::<FakeInner, FakeInner, FakeInner> {
+				data: any.encode_to_vec(),
+				code_id: vec![],
+				latest_height,
+				inner: Box::new(FakeInner),
+				_phantom: Default::default(),
+			};
+		let any = Any {
+			type_url: WASM_CLIENT_STATE_TYPE_URL.to_string(),
+			value: wasm_client_state.encode_vec().unwrap(),
+		};
+		any.encode_to_vec()
+	}
+
+	/// Stores `client_state` wrapped in a wasm client state, the way 08-wasm does on instantiation,
+	/// along with a consensus state at its latest height produced at `timestamp`.
+	fn store_client(
+		deps: DepsMut,
+		env: Env,
+		client_state: ClientState<HostFunctions>,
+		timestamp: Timestamp,
+	) {
+		let height = client_state.latest_height();
+		let mut ctx = Context::<HostFunctions>::new(deps, env);
+		ClientStates::new(ctx.storage_mut()).insert(wasm_client_state(&client_state));
+		let consensus_state = ConsensusState::new(vec![0; 32], timestamp.into_tm_time().unwrap());
+		let client_id = ClientId::from_str("08-wasm-0").unwrap();
+		ctx.store_consensus_state(client_id, height, consensus_state).unwrap();
+	}
+
+	fn query_status(deps: Deps, env: Env) -> String {
+		query_status_response(deps, env).status
+	}
+
+	fn query_status_response(deps: Deps, env: Env) -> QueryResponse {
+		from_binary(&query(deps, env, QueryMsg::Status(StatusMsg {})).unwrap()).unwrap()
+	}
+
+	fn client_state() -> ClientState<HostFunctions> {
+		ClientState {
+			para_id: 2000,
+			latest_para_height: 10,
+			commitment_prefix: IBC_PREFIX.as_bytes().to_vec(),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_status_active() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		store_client(deps.as_mut(), env.clone(), client_state(), now);
+
+		let response = query_status_response(deps.as_ref(), env);
+		assert_eq!(response.status, "Active");
+		assert_eq!(response.frozen_height, None);
+	}
+
+	#[test]
+	fn test_status_frozen() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		let client_state =
+			ClientState { frozen_height: Some(Height::new(2000, 10)), ..client_state() };
+		store_client(deps.as_mut(), env.clone(), client_state, now);
+
+		let response = query_status_response(deps.as_ref(), env);
+		assert_eq!(response.status, "Frozen");
+		assert_eq!(response.frozen_height, Some(Height::new(2000, 10).into()));
+	}
+
+	#[test]
+	fn test_status_expired() {
+		let mut deps = mock_dependencies();
+		let mut env = mock_env();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		let client_state = client_state();
+		let trusting_period = client_state.trusting_period();
+		store_client(deps.as_mut(), env.clone(), client_state, now);
+
+		// still active at the very end of the trusting period
+		env.block.time = env.block.time.plus_nanos(trusting_period.as_nanos() as u64);
+		assert_eq!(query_status(deps.as_ref(), env.clone()), "Active");
+
+		env.block.time = env.block.time.plus_nanos(1);
+		assert_eq!(query_status(deps.as_ref(), env), "Expired");
+	}
+
+	#[test]
+	fn test_status_unknown_without_block_time() {
+		let mut deps = mock_dependencies();
+		let mut env = mock_env();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		store_client(deps.as_mut(), env.clone(), client_state(), now);
+
+		env.block.time = cosmwasm_std::Timestamp::from_nanos(0);
+		assert_eq!(query_status(deps.as_ref(), env), "Unknown");
+	}
+
+	#[test]
+	fn test_status_expired_with_custom_trusting_period() {
+		let mut deps = mock_dependencies();
+		let mut env = mock_env();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		let trusting_period = Duration::from_secs(60 * 60);
+		let client_state =
+			ClientState { custom_trusting_period: Some(trusting_period), ..client_state() };
+		assert_eq!(client_state.trusting_period(), trusting_period);
+		assert_eq!(client_state.unbonding_period(), client_state.relay_chain.unbonding_period());
+		store_client(deps.as_mut(), env.clone(), client_state, now);
+
+		env.block.time = env.block.time.plus_nanos(trusting_period.as_nanos() as u64);
+		assert_eq!(query_status(deps.as_ref(), env.clone()), "Active");
+
+		env.block.time = env.block.time.plus_nanos(1);
+		assert_eq!(query_status(deps.as_ref(), env), "Expired");
+	}
+
+	#[test]
+	fn test_status_uninitialized() {
+		let deps = mock_dependencies();
+		let err = query(deps.as_ref(), mock_env(), QueryMsg::Status(StatusMsg {})).unwrap_err();
+		assert!(err.to_string().contains("contract is not initialized"), "{err}");
+	}
+
+	/// Stores consensus states at the given heights, each processed at host height `1-{n}` and
+	/// time `n` for the n-th of them.
+	fn store_consensus_states(deps: DepsMut, env: Env, heights: &[Height]) {
+		let mut ctx = Context::<HostFunctions>::new(deps, env);
+		let client_id = ClientId::from_str("08-wasm-0").unwrap();
+		for (n, height) in heights.iter().enumerate() {
+			let n = n as u64 + 1;
+			let timestamp = Timestamp::from_nanoseconds(n).unwrap();
+			let consensus_state =
+				ConsensusState::new(vec![0; 32], timestamp.into_tm_time().unwrap());
+			ctx.store_consensus_state(client_id.clone(), *height, consensus_state).unwrap();
+			ctx.store_update_time(client_id.clone(), *height, timestamp).unwrap();
+			ctx.store_update_height(client_id.clone(), *height, Height::new(1, n)).unwrap();
+		}
+	}
+
+	#[test]
+	fn test_export_metadata() {
+		let mut deps = mock_dependencies();
+		let heights = [Height::new(2000, 9), Height::new(2000, 10), Height::new(2000, 100)];
+		store_consensus_states(deps.as_mut(), mock_env(), &heights);
+
+		let response: QueryResponse = from_binary(
+			&query(deps.as_ref(), mock_env(), QueryMsg::ExportMetadata(ExportMetadataMsg {}))
+				.unwrap(),
+		)
+		.unwrap();
+		let metadata = response
+			.genesis_metadata
+			.unwrap()
+			.into_iter()
+			.map(|GenesisMetadata { key, value }| (key, value))
+			.collect::<Vec<_>>();
+
+		let processed_height = |height: &str, n: u64| {
+			(
+				format!("consensusStates/{height}/processedHeight").into_bytes(),
+				format!("1-{n}").into_bytes(),
+			)
+		};
+		let processed_time = |height: &str, n: u64| {
+			(
+				format!("consensusStates/{height}/processedTime").into_bytes(),
+				n.to_be_bytes().to_vec(),
+			)
+		};
+		let iteration = |height: u64| {
+			let key = [
+				b"iterateConsensusStates".as_slice(),
+				&2000u64.to_be_bytes(),
+				&height.to_be_bytes(),
+			]
+			.concat();
+			(key, format!("consensusStates/2000-{height}").into_bytes())
+		};
+		// store keys are ordered lexicographically, so "2000-10" < "2000-100" < "2000-9", while
+		// the iteration keys are ordered by height
+		assert_eq!(
+			metadata,
+			vec![
+				processed_height("2000-10", 2),
+				processed_time("2000-10", 2),
+				processed_height("2000-100", 3),
+				processed_time("2000-100", 3),
+				processed_height("2000-9", 1),
+				processed_time("2000-9", 1),
+				iteration(9),
+				iteration(10),
+				iteration(100),
+			]
+		);
+	}
+
+	#[test]
+	fn test_get_latest_heights() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		store_client(deps.as_mut(), env, client_state(), now);
+		// only the highest of the other consensus states is returned
+		let heights = [Height::new(2000, 9), Height::new(2000, 100), Height::new(2000, 12)];
+		store_consensus_states(deps.as_mut(), mock_env(), &heights);
+
+		let response: QueryResponse = from_binary(
+			&query(
+				deps.as_ref(),
+				mock_env(),
+				QueryMsg::GetLatestHeightsMsg(GetLatestHeightsMsg {}),
+			)
+			.unwrap(),
+		)
+		.unwrap();
+		let heights = response.heights.unwrap().into_iter().map(Height::from).collect::<Vec<_>>();
+		assert_eq!(heights, vec![Height::new(2000, 100), client_state().latest_height()]);
+	}
+
+	#[test]
+	fn test_iterate_consensus_states() {
+		let mut deps = mock_dependencies();
+		let heights = [100, 9, 12, 10].map(|height| Height::new(2000, height));
+		store_consensus_states(deps.as_mut(), mock_env(), &heights);
+
+		let iterate = |start_height: Option<u64>, limit| {
+			let msg = QueryMsg::IterateConsensusStates(IterateConsensusStatesMsg {
+				start_height: start_height.map(|height| Height::new(2000, height).into()),
+				limit,
+			});
+			let response: QueryResponse =
+				from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+			response.consensus_states.unwrap()
+		};
+		let client_id = ClientId::from_str("08-wasm-0").unwrap();
+		let page_heights = |start_height, limit| {
+			iterate(start_height, limit)
+				.into_iter()
+				.map(|entry| {
+					let height = Height::from(entry.height);
+					let stored = get_consensus_state(deps.as_ref(), &client_id, height).unwrap();
+					assert_eq!(entry.consensus_state, stored);
+					height.revision_height
+				})
+				.collect::<Vec<_>>()
+		};
+
+		assert_eq!(page_heights(None, 2), vec![9, 10]);
+		// the start height is included, and needn't be the height of a stored consensus state
+		assert_eq!(page_heights(Some(10), 2), vec![10, 12]);
+		assert_eq!(page_heights(Some(11), 2), vec![12, 100]);
+		assert_eq!(page_heights(Some(11), 10), vec![12, 100]);
+		assert_eq!(page_heights(Some(101), 2), Vec::<u64>::new());
+		assert_eq!(page_heights(None, 0), Vec::<u64>::new());
+	}
+
+	/// Stores `client_state`, a consensus state at its latest height and the time and height at
+	/// which it was processed under `prefix`, the way 08-wasm lays out the subject and substitute
+	/// clients for client recovery.
+	fn store_prefixed_client(
+		deps: DepsMut,
+		env: Env,
+		client_state: ClientState<HostFunctions>,
+		prefix: &[u8],
+	) {
+		let height = client_state.latest_height();
+		let mut ctx = Context::<HostFunctions>::new(deps, env);
+		let (host_timestamp, host_height) = (ctx.host_timestamp(), ctx.host_height());
+		ClientStates::new(ctx.storage_mut())
+			.insert_prefixed(wasm_client_state(&client_state), prefix);
+		let consensus_state =
+			ConsensusState::new(vec![1; 32], host_timestamp.into_tm_time().unwrap());
+		ctx.store_consensus_state_prefixed(height, consensus_state, prefix);
+		let key = (b"08-wasm-0".to_vec(), height.to_string().into_bytes());
+		let processed_time = cosmwasm_std::to_vec(&host_timestamp.nanoseconds()).unwrap();
+		let processed_height = cosmwasm_std::to_vec(&host_height.to_string().into_bytes()).unwrap();
+		ctx.storage_mut()
+			.set(&[prefix, &CLIENT_UPDATE_TIME.key(key.clone())].concat(), &processed_time);
+		ctx.storage_mut()
+			.set(&[prefix, &CLIENT_UPDATE_HEIGHT.key(key)].concat(), &processed_height);
+	}
+
+	fn check_substitute_and_update_state(deps: DepsMut, env: Env) -> Result<(), ContractError> {
+		let msg = ExecuteMsg::CheckSubstituteAndUpdateState(CheckSubstituteAndUpdateStateMsgRaw {});
+		execute(deps, env, mock_info("sender", &[]), msg).map(|_| ())
+	}
+
+	#[test]
+	fn test_check_substitute_and_update_state() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let subject = ClientState { frozen_height: Some(Height::new(2000, 10)), ..client_state() };
+		let substitute = ClientState {
+			latest_para_height: 20,
+			latest_relay_height: 100,
+			latest_relay_hash: H256::repeat_byte(1),
+			current_set_id: 1,
+			..client_state()
+		};
+		store_prefixed_client(deps.as_mut(), env.clone(), subject, SUBJECT_PREFIX);
+		store_prefixed_client(deps.as_mut(), env.clone(), substitute.clone(), SUBSTITUTE_PREFIX);
+
+		check_substitute_and_update_state(deps.as_mut(), env.clone()).unwrap();
+
+		// the subject is unfrozen and tracks the substitute's latest consensus state
+		let ctx = Context::<HostFunctions>::new(deps.as_mut(), env);
+		let recovered = ctx.client_state_prefixed(SUBJECT_PREFIX).unwrap();
+		assert_eq!(recovered, substitute);
+		let height = Height::new(2000, 20);
+		assert_eq!(
+			ctx.consensus_state_prefixed(height, SUBJECT_PREFIX).unwrap(),
+			ctx.consensus_state_prefixed(height, SUBSTITUTE_PREFIX).unwrap()
+		);
+		let key = (b"08-wasm-0".to_vec(), height.to_string().into_bytes());
+		for key in
+			[CLIENT_UPDATE_TIME.key(key.clone()).to_vec(), CLIENT_UPDATE_HEIGHT.key(key).to_vec()]
+		{
+			assert_eq!(
+				ctx.storage().get(&[SUBJECT_PREFIX, &key].concat()),
+				ctx.storage().get(&[SUBSTITUTE_PREFIX, &key].concat())
+			);
+		}
+	}
+
+	#[test]
+	fn test_check_substitute_with_different_chain_id() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let subject = ClientState { frozen_height: Some(Height::new(2000, 10)), ..client_state() };
+		let substitute = ClientState { para_id: 2001, latest_para_height: 20, ..client_state() };
+		store_prefixed_client(deps.as_mut(), env.clone(), subject.clone(), SUBJECT_PREFIX);
+		store_prefixed_client(deps.as_mut(), env.clone(), substitute, SUBSTITUTE_PREFIX);
+
+		let err = check_substitute_and_update_state(deps.as_mut(), env.clone()).unwrap_err();
+		assert!(err.to_string().contains("does not match subject client chain id"), "{err}");
+
+		// the subject is left untouched
+		let ctx = Context::<HostFunctions>::new(deps.as_mut(), env);
+		assert_eq!(ctx.client_state_prefixed(SUBJECT_PREFIX).unwrap(), subject);
+	}
+
+	#[test]
+	fn test_check_substitute_with_different_commitment_prefix() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let subject = client_state();
+		let substitute = ClientState {
+			latest_para_height: 20,
+			commitment_prefix: b"other/".to_vec(),
+			..client_state()
+		};
+		store_prefixed_client(deps.as_mut(), env.clone(), subject, SUBJECT_PREFIX);
+		store_prefixed_client(deps.as_mut(), env.clone(), substitute, SUBSTITUTE_PREFIX);
+
+		let err = check_substitute_and_update_state(deps.as_mut(), env).unwrap_err();
+		assert!(err.to_string().contains("[\"commitment_prefix\"]"), "{err}");
+	}
+
+	#[test]
+	fn test_get_latest_heights_after_instantiation() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		let client_state = client_state();
+		store_client(deps.as_mut(), env.clone(), client_state.clone(), now);
+
+		let response: QueryResponse = from_binary(
+			&query(deps.as_ref(), env, QueryMsg::GetLatestHeightsMsg(GetLatestHeightsMsg {}))
+				.unwrap(),
+		)
+		.unwrap();
+		let heights = response.heights.unwrap().into_iter().map(Height::from).collect::<Vec<_>>();
+		assert_eq!(heights, vec![client_state.latest_height()]);
+	}
+
+	#[test]
+	fn test_store_consensus_states_batch() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		store_client(deps.as_mut(), env.clone(), client_state(), now);
+
+		let mut ctx = Context::<HostFunctions>::new(deps.as_mut(), env);
+		let client_id = ClientId::from_str("08-wasm-0").unwrap();
+		let heights = [11, 12, 13].map(|height| Height::new(2000, height));
+		let consensus_states = heights
+			.iter()
+			.map(|height| {
+				let root = vec![height.revision_height as u8; 32];
+				(*height, ConsensusState::new(root, now.into_tm_time().unwrap()))
+			})
+			.collect::<Vec<_>>();
+		let client_state = ClientState { latest_para_height: 13, ..client_state() };
+		let response = store_client_and_consensus_states(
+			&mut ctx,
+			client_id.clone(),
+			client_state,
+			ConsensusUpdateResult::Batch(consensus_states.clone()),
+		)
+		.unwrap()
+		.unwrap();
+
+		let result: ContractResult = from_binary(&response).unwrap();
+		let response_heights =
+			result.heights.unwrap().into_iter().map(Height::from).collect::<Vec<_>>();
+		assert_eq!(response_heights, heights);
+		for (height, consensus_state) in consensus_states {
+			assert_eq!(ctx.consensus_state(&client_id, height).unwrap(), consensus_state);
+			ctx.processed_timestamp(&client_id, height).unwrap();
+		}
+		assert_eq!(ctx.client_state(&client_id).unwrap().latest_para_height, 13);
+	}
+
+	#[test]
+	fn test_timestamp_at_height() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		store_client(deps.as_mut(), env.clone(), client_state(), now);
+
+		let query_timestamp = |height| {
+			let msg = QueryMsg::TimestampAtHeight(TimestampAtHeightMsg {
+				height: Height::new(2000, height).into(),
+			});
+			query(deps.as_ref(), env.clone(), msg)
+		};
+		let response: QueryResponse = from_binary(&query_timestamp(10).unwrap()).unwrap();
+		assert_eq!(response.timestamp, Some(now.nanoseconds()));
+
+		let err = query_timestamp(11).unwrap_err();
+		assert!(err.to_string().contains("consensus state not found"), "{err}");
+	}
+
+	const IBC_PREFIX: &str = "ibc/";
+
+	fn commitment_path(sequence: u64) -> String {
+		format!("commitments/ports/transfer/channels/channel-0/sequences/{sequence}")
+	}
+
+	/// Stores `client_state` with a consensus state whose root is the one of a relay chain state
+	/// holding `items` in its ibc child trie. Returns a proof of `items` and of `absent` paths.
+	fn store_client_with_proof(
+		deps: DepsMut,
+		env: Env,
+		client_state: ClientState<HostFunctions>,
+		items: &[(String, Vec<u8>)],
+		absent: &[String],
+	) -> Vec<u8> {
+		let child_info = ChildInfo::new_default(IBC_PREFIX.as_bytes());
+		let key = |path: &String| [IBC_PREFIX.as_bytes(), path.as_bytes()].concat();
+		let data = items
+			.iter()
+			.map(|(path, value)| (key(path), value.encode()))
+			.collect::<BTreeMap<_, _>>();
+		let keys = data.keys().cloned().chain(absent.iter().map(key)).collect::<Vec<_>>();
+		let storage = Storage {
+			top: Default::default(),
+			children_default: [(
+				child_info.storage_key().to_vec(),
+				StorageChild { data, child_info: child_info.clone() },
+			)]
+			.into_iter()
+			.collect(),
+		};
+		let backend = InMemoryBackend::<BlakeTwo256>::from((storage, StateVersion::V0));
+		let root = backend.root().as_bytes().to_vec();
+		let proof = prove_child_read(backend, &child_info, keys).unwrap();
+
+		let height = client_state.latest_height();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		let mut ctx = Context::<HostFunctions>::new(deps, env);
+		ClientStates::new(ctx.storage_mut()).insert(wasm_client_state(&client_state));
+		let consensus_state = ConsensusState::new(root, now.into_tm_time().unwrap());
+		let client_id = ClientId::from_str("08-wasm-0").unwrap();
+		ctx.store_consensus_state(client_id, height, consensus_state).unwrap();
+		proof.into_nodes().into_iter().collect::<Vec<_>>().encode()
+	}
+
+	fn membership_item(proof: &[u8], path: &str, value: Vec<u8>) -> VerifyMembershipMsgRaw {
+		VerifyMembershipMsgRaw {
+			proof: proof.to_vec(),
+			path: MerklePath { key_path: vec![IBC_PREFIX.to_string(), path.to_string()] },
+			value,
+			height: Height::new(2000, 10).into(),
+			delay_block_period: 0,
+			delay_time_period: 0,
+		}
+	}
+
+	fn non_membership_item(proof: &[u8], path: &str) -> VerifyNonMembershipMsgRaw {
+		VerifyNonMembershipMsgRaw {
+			proof: proof.to_vec(),
+			path: MerklePath { key_path: vec![IBC_PREFIX.to_string(), path.to_string()] },
+			height: Height::new(2000, 10).into(),
+			delay_block_period: 0,
+			delay_time_period: 0,
+		}
+	}
+
+	fn execute_result(deps: DepsMut, env: Env, msg: ExecuteMsg) -> ContractResult {
+		let response = execute(deps, env, mock_info("sender", &[]), msg).unwrap();
+		from_binary(&response.data.unwrap()).unwrap()
+	}
+
+	fn items_validity(result: ContractResult) -> Vec<bool> {
+		result.results.unwrap().iter().map(|result| result.is_valid).collect()
+	}
+
+	#[test]
+	fn test_verify_membership_batch() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let items = (1..=3)
+			.map(|seq| (commitment_path(seq), vec![seq as u8; 32]))
+			.collect::<Vec<_>>();
+		let proof =
+			store_client_with_proof(deps.as_mut(), env.clone(), client_state(), &items, &[]);
+
+		let mut batch = items
+			.iter()
+			.map(|(path, value)| membership_item(&proof, path, value.clone()))
+			.collect::<Vec<_>>();
+		let result = execute_result(
+			deps.as_mut(),
+			env.clone(),
+			ExecuteMsg::VerifyMembershipBatch(batch.clone()),
+		);
+		assert!(result.is_valid, "{}", result.error_msg);
+		assert_eq!(items_validity(result), vec![true; 3]);
+
+		// the second item fails on its own, and is named in the batch result
+		batch[1].value = vec![0; 32];
+		let result = execute_result(
+			deps.as_mut(),
+			env.clone(),
+			ExecuteMsg::VerifyMembershipBatch(batch.clone()),
+		);
+		assert!(!result.is_valid);
+		assert!(result.error_msg.starts_with("item 1: "), "{}", result.error_msg);
+		assert_eq!(items_validity(result), vec![true, false, true]);
+
+		// single items are still verified on their own
+		let msg = ExecuteMsg::VerifyMembership(batch[0].clone());
+		execute(deps.as_mut(), env.clone(), mock_info("sender", &[]), msg).unwrap();
+		let msg = ExecuteMsg::VerifyMembership(batch[1].clone());
+		execute(deps.as_mut(), env, mock_info("sender", &[]), msg).unwrap_err();
+	}
+
+	#[test]
+	fn test_verify_non_membership_batch() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let items = vec![(commitment_path(2), vec![2; 32])];
+		let absent = [commitment_path(1), commitment_path(3)];
+		let proof =
+			store_client_with_proof(deps.as_mut(), env.clone(), client_state(), &items, &absent);
+
+		let mut batch = [&absent[0], &items[0].0, &absent[1]]
+			.into_iter()
+			.map(|path| non_membership_item(&proof, path))
+			.collect::<Vec<_>>();
+		let result = execute_result(
+			deps.as_mut(),
+			env.clone(),
+			ExecuteMsg::VerifyNonMembershipBatch(batch.clone()),
+		);
+		assert!(!result.is_valid);
+		assert!(result.error_msg.starts_with("item 1: "), "{}", result.error_msg);
+		assert_eq!(items_validity(result), vec![true, false, true]);
+
+		// a malformed item fails without affecting the others
+		batch[1].path.key_path.truncate(1);
+		let result =
+			execute_result(deps.as_mut(), env, ExecuteMsg::VerifyNonMembershipBatch(batch));
+		assert!(result.error_msg.starts_with("item 1: "), "{}", result.error_msg);
+		assert_eq!(items_validity(result), vec![true, false, true]);
+	}
+
+	#[test]
+	fn test_verify_membership_at_unknown_height() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let items = vec![(commitment_path(1), vec![1; 32])];
+		let absent = [commitment_path(2)];
+		let proof =
+			store_client_with_proof(deps.as_mut(), env.clone(), client_state(), &items, &absent);
+
+		// the proof is valid against the consensus state at the client's latest height
+		let mut item = membership_item(&proof, &items[0].0, items[0].1.clone());
+		let msg = ExecuteMsg::VerifyMembership(item.clone());
+		execute(deps.as_mut(), env.clone(), mock_info("sender", &[]), msg).unwrap();
+
+		// but there is no consensus state to verify it against at any other height
+		item.height = Height::new(2000, 11).into();
+		let msg = ExecuteMsg::VerifyMembership(item);
+		let err = execute(deps.as_mut(), env.clone(), mock_info("sender", &[]), msg).unwrap_err();
+		assert!(matches!(err, ContractError::InvalidHeight(_)), "{err}");
+
+		let mut item = non_membership_item(&proof, &absent[0]);
+		item.height = Height::new(2000, 9).into();
+		let msg = ExecuteMsg::VerifyNonMembership(item);
+		let err = execute(deps.as_mut(), env, mock_info("sender", &[]), msg).unwrap_err();
+		assert!(matches!(err, ContractError::InvalidHeight(_)), "{err}");
+	}
+
+	fn authority_pairs() -> Vec<ed25519::Pair> {
+		(0..3).map(|i| ed25519::Pair::from_seed(&[i; 32])).collect()
+	}
+
+	/// A client whose current authority set is made of the [`authority_pairs`].
+	fn misbehaviour_client_state() -> ClientState<HostFunctions> {
+		ClientState {
+			latest_relay_height: 50,
+			latest_relay_hash: H256::repeat_byte(9),
+			current_set_id: 1,
+			current_authorities: authority_pairs()
+				.iter()
+				.map(|pair| (pair.public().into(), 1))
+				.collect(),
+			..client_state()
+		}
+	}
+
+	/// Builds a finality proof for `blocks` relay chain blocks on top of the client's latest relay
+	/// chain block, on the fork identified by `fork`, signed by the client's authorities.
+	fn signed_finality_proof(
+		client_state: &ClientState<HostFunctions>,
+		fork: u8,
+		blocks: u32,
+	) -> FinalityProof<RelayChainHeader> {
+		let mut parent_hash = client_state.latest_relay_hash;
+		let unknown_headers = (1..=blocks)
+			.map(|i| {
+				let header = RelayChainHeader::new(
+					client_state.latest_relay_height + i,
+					Default::default(),
+					H256::repeat_byte(fork),
+					parent_hash,
+					Default::default(),
+				);
+				parent_hash = header.hash();
+				header
+			})
+			.collect::<Vec<_>>();
+		let target = unknown_headers.last().unwrap();
+		let precommit = finality_grandpa::Precommit {
+			target_hash: target.hash(),
+			target_number: target.number,
+		};
+		let message = finality_grandpa::Message::Precommit(precommit.clone());
+		let precommits = authority_pairs()
+			.iter()
+			.map(|pair| finality_grandpa::SignedPrecommit {
+				precommit: precommit.clone(),
+				signature: pair
+					.sign(&(message.clone(), 1u64, client_state.current_set_id).encode())
+					.into(),
+				id: pair.public().into(),
+			})
+			.collect();
+		let justification = GrandpaJustification::<RelayChainHeader> {
+			round: 1,
+			commit: finality_grandpa::Commit {
+				target_hash: target.hash(),
+				target_number: target.number,
+				precommits,
+			},
+			votes_ancestries: vec![],
+		};
+		FinalityProof {
+			block: target.hash(),
+			justification: justification.encode(),
+			unknown_headers,
+		}
+	}
+
+	fn misbehaviour_message(
+		first_finality_proof: FinalityProof<RelayChainHeader>,
+		second_finality_proof: FinalityProof<RelayChainHeader>,
+	) -> ClientMessageRaw {
+		let misbehaviour = Misbehaviour { first_finality_proof, second_finality_proof };
+		let any = Any {
+			type_url: GRANDPA_MISBEHAVIOUR_TYPE_URL.to_string(),
+			value: misbehaviour.encode_vec().unwrap(),
+		};
+		ClientMessageRaw::Misbehaviour(WasmMisbehaviour { data: any.encode_to_vec() })
+	}
+
+	fn verify_client_message(
+		deps: DepsMut,
+		env: Env,
+		client_message: ClientMessageRaw,
+	) -> Result<Response, ContractError> {
+		let msg = ExecuteMsg::VerifyClientMessage(VerifyClientMessageRaw { client_message });
+		execute(deps, env, mock_info("sender", &[]), msg)
+	}
+
+	fn check_for_misbehaviour(deps: DepsMut, env: Env, client_message: ClientMessageRaw) -> bool {
+		let msg = ExecuteMsg::CheckForMisbehaviour(CheckForMisbehaviourMsgRaw { client_message });
+		execute_result(deps, env, msg).found_misbehaviour
+	}
+
+	#[test]
+	fn test_check_for_misbehaviour() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		let client_state = misbehaviour_client_state();
+		store_client(deps.as_mut(), env.clone(), client_state.clone(), now);
+		Context::<HostFunctions>::new(deps.as_mut(), env.clone())
+			.insert_relay_header_hashes(&[client_state.latest_relay_hash]);
+
+		// two validly signed finality proofs for conflicting forks of the relay chain
+		let client_message = misbehaviour_message(
+			signed_finality_proof(&client_state, 1, 2),
+			signed_finality_proof(&client_state, 2, 2),
+		);
+		verify_client_message(deps.as_mut(), env.clone(), client_message.clone()).unwrap();
+		assert!(check_for_misbehaviour(deps.as_mut(), env.clone(), client_message));
+
+		// the second proof finalizes a descendant of the first one's block, they don't conflict
+		let client_message = misbehaviour_message(
+			signed_finality_proof(&client_state, 1, 2),
+			signed_finality_proof(&client_state, 1, 3),
+		);
+		verify_client_message(deps.as_mut(), env.clone(), client_message.clone()).unwrap();
+		assert!(!check_for_misbehaviour(deps.as_mut(), env.clone(), client_message));
+
+		// a header without parachain headers can't conflict with the stored consensus states
+		let header = GrandpaHeader {
+			finality_proof: signed_finality_proof(&client_state, 1, 2),
+			parachain_headers: Default::default(),
+			height: Height::new(2000, 11),
+		};
+		let any = Any {
+			type_url: GRANDPA_HEADER_TYPE_URL.to_string(),
+			value: header.encode_vec().unwrap(),
+		};
+		let client_message = ClientMessageRaw::Header(WasmHeader {
+			inner: Box::new(FakeInner),
+			data: any.encode_to_vec(),
+			height: Height::new(2000, 11),
+		});
+		assert!(!check_for_misbehaviour(deps.as_mut(), env, client_message));
+	}
+
+	#[test]
+	fn test_instantiate_max_consensus_states() {
+		let mut deps = mock_dependencies();
+		let (env, info) = (mock_env(), mock_info("sender", &[]));
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		store_client(deps.as_mut(), env.clone(), client_state(), now);
+		let msg = |max_consensus_states| InstantiateMsg { max_consensus_states };
+
+		let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg(Some(0))).unwrap_err();
+		assert!(err.to_string().contains("max_consensus_states must be at least 1"), "{err}");
+
+		let max_consensus_states = |deps: DepsMut| {
+			let ctx = Context::<HostFunctions>::new(deps, mock_env());
+			let client_id = ClientId::from_str("08-wasm-0").unwrap();
+			ctx.client_state(&client_id).unwrap().max_consensus_states
+		};
+		instantiate(deps.as_mut(), env.clone(), info.clone(), msg(None)).unwrap();
+		assert_eq!(max_consensus_states(deps.as_mut()), None);
+		instantiate(deps.as_mut(), env, info, msg(Some(3))).unwrap();
+		assert_eq!(max_consensus_states(deps.as_mut()), Some(3));
+	}
+
+	#[test]
+	fn test_instantiate_validates_initial_states() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let instantiate_client = |deps: DepsMut| {
+			instantiate(deps, env.clone(), mock_info("sender", &[]), InstantiateMsg::default())
+		};
+
+		let err = instantiate_client(deps.as_mut()).unwrap_err();
+		assert!(err.to_string().contains("client state is not stored"), "{err}");
+
+		// a tendermint client state handed to the grandpa contract
+		let any = Any {
+			type_url: "/ibc.lightclients.tendermint.v1.ClientState".to_string(),
+			value: vec![1, 2, 3],
+		};
+		let data = wasm_client_state_of_any(any, Height::new(1, 10));
+		ClientStates::new(deps.as_mut().storage).insert(data);
+		let err = instantiate_client(deps.as_mut()).unwrap_err();
+		assert!(
+			err.to_string().contains(
+				"unexpected client state type /ibc.lightclients.tendermint.v1.ClientState"
+			),
+			"{err}"
+		);
+
+		// a grandpa client state without a consensus state at its latest height
+		let client_state = client_state();
+		ClientStates::new(deps.as_mut().storage).insert(wasm_client_state(&client_state));
+		let err = instantiate_client(deps.as_mut()).unwrap_err();
+		assert!(err.to_string().contains("consensus state at height 2000-10"), "{err}");
+
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		// a grandpa client state that isn't bound to a commitment prefix
+		let unbound = ClientState { commitment_prefix: vec![], ..client_state.clone() };
+		store_client(deps.as_mut(), env.clone(), unbound, now);
+		let err = instantiate_client(deps.as_mut()).unwrap_err();
+		assert!(err.to_string().contains("isn't bound to a commitment prefix"), "{err}");
+
+		store_client(deps.as_mut(), env.clone(), client_state, now);
+		instantiate_client(deps.as_mut()).unwrap();
+	}
+
+	#[test]
+	fn test_client_type() {
+		let deps = mock_dependencies();
+		let response: QueryResponse = from_binary(
+			&query(deps.as_ref(), mock_env(), QueryMsg::ClientTypeMsg(ClientTypeMsg {})).unwrap(),
+		)
+		.unwrap();
+		assert_eq!(response.client_type.as_deref(), Some("10-grandpa"));
+	}
+
+	fn migrate_from(deps: DepsMut, env: Env, from_version: &str) -> Result<(), ContractError> {
+		let msg = MigrateMsg::Migrate { from_version: from_version.to_string() };
+		migrate(deps, env, msg).map(|_| ())
+	}
+
+	#[test]
+	fn test_migrate_from_legacy_layout() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		store_client(deps.as_mut(), env.clone(), client_state(), now);
+		// the legacy layout stored the host block height as the revision number
+		let client_id = ClientId::from_str("08-wasm-0").unwrap();
+		let height = Height::new(2000, 10);
+		let mut ctx = Context::<HostFunctions>::new(deps.as_mut(), env.clone());
+		ctx.store_update_time(client_id.clone(), height, now).unwrap();
+		ctx.store_update_height(client_id.clone(), height, Height::new(env.block.height, 0))
+			.unwrap();
+
+		migrate_from(deps.as_mut(), env.clone(), LEGACY_CONTRACT_VERSION).unwrap();
+
+		let stored = CONTRACT_INFO.load(deps.as_ref().storage).unwrap();
+		assert_eq!(
+			(stored.contract.as_str(), stored.version.as_str()),
+			(CONTRACT_NAME, CONTRACT_VERSION)
+		);
+		let ctx = Context::<HostFunctions>::new(deps.as_mut(), env.clone());
+		assert_eq!(ctx.processed_height(&client_id, height).unwrap(), ctx.host_height());
+		assert_eq!(ctx.processed_timestamp(&client_id, height).unwrap(), now.nanoseconds());
+		assert_eq!(ctx.client_state(&client_id).unwrap(), client_state());
+
+		assert_eq!(query_status(deps.as_ref(), env.clone()), "Active");
+		let msg = QueryMsg::TimestampAtHeight(TimestampAtHeightMsg { height: height.into() });
+		let response: QueryResponse =
+			from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+		assert_eq!(response.timestamp, Some(now.nanoseconds()));
+	}
+
+	#[test]
+	fn test_migrate_checks_versions() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let err = migrate_from(deps.as_mut(), env.clone(), "0.0.1").unwrap_err();
+		assert!(err.to_string().contains("unknown contract version 0.0.1"), "{err}");
+
+		// the stored version must be the one migrated from
+		let now = Timestamp::from_nanoseconds(env.block.time.nanos()).unwrap();
+		store_client(deps.as_mut(), env.clone(), client_state(), now);
+		instantiate(
+			deps.as_mut(),
+			env.clone(),
+			mock_info("sender", &[]),
+			InstantiateMsg::default(),
+		)
+		.unwrap();
+		let err = migrate_from(deps.as_mut(), env.clone(), LEGACY_CONTRACT_VERSION).unwrap_err();
+		assert!(err.to_string().contains("doesn't match the version"), "{err}");
+		migrate_from(deps.as_mut(), env, CONTRACT_VERSION).unwrap();
+	}
+}
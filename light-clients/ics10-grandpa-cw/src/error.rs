@@ -46,6 +46,35 @@ pub enum ContractError {
 	ProtoDecode(prost::DecodeError),
 	#[display(fmt = "From UTF8 error: {_0}")]
 	FromUtf8(alloc::string::FromUtf8Error),
+	#[display(
+		fmt = "Invalid merkle path: expected a commitment prefix and an IBC path, got {_0} segment(s)"
+	)]
+	#[from(ignore)]
+	InvalidMerklePath(usize),
+	#[display(fmt = "Invalid migration: {_0}")]
+	#[from(ignore)]
+	InvalidMigration(String),
+	#[display(fmt = "Invalid proof: {_0}")]
+	#[from(ignore)]
+	InvalidProof(String),
+	#[display(fmt = "Invalid path {value:?}: {source}")]
+	#[from(ignore)]
+	InvalidPath {
+		value: String,
+		source: ibc::core::ics24_host::path::PathError,
+	},
+	#[display(fmt = "Invalid client message: {_0}")]
+	#[from(ignore)]
+	InvalidClientMessage(String),
+	#[display(fmt = "Invalid height: {_0}")]
+	#[from(ignore)]
+	InvalidHeight(String),
+	#[display(fmt = "Invalid instantiate message: {_0}")]
+	#[from(ignore)]
+	InvalidInstantiateMsg(String),
+	#[display(fmt = "Invalid initial state: {_0}")]
+	#[from(ignore)]
+	InvalidInitialState(String),
 }
 
 impl Error for ContractError {}
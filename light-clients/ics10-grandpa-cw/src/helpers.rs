@@ -16,9 +16,37 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{to_binary, Addr, CosmosMsg, StdResult, WasmMsg};
+use cosmwasm_std::{to_binary, Addr, CosmosMsg, Order, StdResult, Storage, WasmMsg};
+use grandpa_light_client_primitives::HostFunctions;
+use ibc::{
+	core::{
+		ics02_client::{client_consensus::ConsensusState as _, context::ClientReader},
+		ics24_host::identifier::{ChainId, ClientId},
+	},
+	protobuf::Protobuf,
+	Height,
+};
+use ibc_proto::google::protobuf::Any;
+use ics08_wasm::{
+	client_state::ClientState as WasmClientState,
+	consensus_state::ConsensusState as WasmConsensusState,
+};
+use ics10_grandpa::{
+	client_message::RelayChainHeader,
+	client_state::{ClientState, GRANDPA_CLIENT_STATE_TYPE_URL},
+	consensus_state::GRANDPA_CONSENSUS_STATE_TYPE_URL,
+};
+use prost::Message;
 
-use crate::msg::ExecuteMsg;
+use crate::{
+	context::Context,
+	contract::{CLIENT_UPDATE_HEIGHT, CLIENT_UPDATE_TIME},
+	ics23::{
+		ClientStates, ConsensusStates, FakeInner, ReadonlyClientStates, ReadonlyConsensusStates,
+	},
+	msg::ExecuteMsg,
+	ContractError,
+};
 
 /// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
 /// for working with this.
@@ -35,3 +63,317 @@ impl CwTemplateContract {
 		Ok(WasmMsg::Execute { contract_addr: self.addr().into(), msg, funds: vec![] }.into())
 	}
 }
+
+/// Checks that both the delay time period and the delay block period have passed on the host
+/// since the consensus state at `height` was stored. Nothing is checked if both periods are zero.
+pub fn verify_delay_passed<H: HostFunctions<Header = RelayChainHeader>>(
+	ctx: &Context<H>,
+	client_id: &ClientId,
+	height: Height,
+	delay_period_time: u64,
+	delay_period_blocks: u64,
+) -> Result<(), ContractError> {
+	if delay_period_time == 0 && delay_period_blocks == 0 {
+		return Ok(())
+	}
+
+	let current_time = ctx.host_timestamp().nanoseconds();
+	let current_height = ctx.host_height();
+
+	let processed_time = ctx.processed_timestamp(client_id, height)?;
+	let processed_height = ctx.processed_height(client_id, height)?;
+
+	// NOTE: the delay time period is inclusive, so `current_time == earliest_time` is accepted.
+	let earliest_time = processed_time
+		.checked_add(delay_period_time)
+		.ok_or_else(|| ContractError::Grandpa("delay time period overflowed".to_string()))?;
+	if current_time < earliest_time {
+		return Err(ContractError::Grandpa(format!(
+			"not enough time elapsed, current time: {current_time}, earliest time: {earliest_time}"
+		)))
+	}
+
+	let earliest_height = processed_height.add(delay_period_blocks);
+	if current_height < earliest_height {
+		return Err(ContractError::Grandpa(format!(
+			"not enough blocks elapsed, current height: {current_height}, earliest height: {earliest_height}"
+		)))
+	}
+
+	Ok(())
+}
+
+/// Removes the consensus states which are older than the client's trusting period, starting from
+/// the oldest one and stopping at the first one which hasn't expired. Only the heights are read
+/// up front, each consensus state is loaded at most once. The consensus state at the client's
+/// latest height is never removed.
+pub fn prune_expired_consensus_states<H: HostFunctions<Header = RelayChainHeader>>(
+	ctx: &mut Context<H>,
+	client_id: &ClientId,
+	client_state: &ClientState<H>,
+) -> Result<(), ContractError> {
+	let now = ctx.host_timestamp();
+	let latest_height = client_state.latest_height();
+	let heights = ReadonlyConsensusStates::new(ctx.storage()).heights();
+	for height in heights.into_iter().take_while(|height| *height < latest_height) {
+		let consensus_state = ctx
+			.consensus_state(client_id, height)
+			.map_err(|e| ContractError::Grandpa(e.to_string()))?;
+		let expired = now
+			.duration_since(&consensus_state.timestamp())
+			.map_or(false, |elapsed| client_state.expired(elapsed));
+		if !expired {
+			break
+		}
+		remove_consensus_state(ctx, client_id, height);
+	}
+	Ok(())
+}
+
+/// Removes the oldest consensus states so that at most `max_consensus_states` of them are kept,
+/// without loading any of them. The consensus state at `latest_height` is never removed.
+pub fn prune_excess_consensus_states<H: HostFunctions<Header = RelayChainHeader>>(
+	ctx: &mut Context<H>,
+	client_id: &ClientId,
+	latest_height: Height,
+	max_consensus_states: u32,
+) {
+	let heights = ReadonlyConsensusStates::new(ctx.storage()).heights();
+	let excess = heights.len().saturating_sub(max_consensus_states as usize);
+	for height in heights.into_iter().filter(|height| *height != latest_height).take(excess) {
+		remove_consensus_state(ctx, client_id, height);
+	}
+}
+
+/// Sets the maximum number of consensus states given on instantiation in the stored client state.
+pub fn set_max_consensus_states<H: HostFunctions<Header = RelayChainHeader>>(
+	storage: &mut dyn Storage,
+	max_consensus_states: u32,
+) -> Result<(), ContractError> {
+	let data = ReadonlyClientStates::new(storage)
+		.get()
+		.ok_or_else(|| ContractError::Grandpa("client state is not stored".to_string()))?;
+	let client_state = Context::<H>::decode_client_state(&data)
+		.map_err(|e| ContractError::Grandpa(format!("error decoding client state: {e}")))?;
+	let client_state =
+		ClientState { max_consensus_states: Some(max_consensus_states), ..client_state };
+	let encoded = Context::<H>::encode_client_state(client_state, data)
+		.map_err(|e| ContractError::Grandpa(format!("error encoding client state: {e}")))?;
+	ClientStates::new(storage).insert(encoded);
+	Ok(())
+}
+
+/// Removes the consensus state at `height` along with the time and height it was processed at.
+fn remove_consensus_state<H: HostFunctions<Header = RelayChainHeader>>(
+	ctx: &mut Context<H>,
+	client_id: &ClientId,
+	height: Height,
+) {
+	ConsensusStates::new(ctx.storage_mut()).remove(height);
+	let key = (client_id.as_bytes().to_owned(), height.to_string().into_bytes());
+	CLIENT_UPDATE_TIME.remove(ctx.storage_mut(), key.clone());
+	CLIENT_UPDATE_HEIGHT.remove(ctx.storage_mut(), key);
+}
+
+/// Checks that the client state stored by 08-wasm before instantiating the contract and the
+/// consensus state at its latest height are grandpa states, so that a contract instantiated for
+/// another client type fails right away rather than on its first update.
+pub fn validate_initial_states<H: HostFunctions<Header = RelayChainHeader>>(
+	storage: &dyn Storage,
+) -> Result<(), ContractError> {
+	let invalid = |e: &dyn core::fmt::Display| ContractError::InvalidInitialState(e.to_string());
+	let data = ReadonlyClientStates::new(storage)
+		.get()
+		.ok_or_else(|| invalid(&"client state is not stored"))?;
+	let any = Any::decode(&*data).map_err(|e| invalid(&e))?;
+	let wasm_client_state =
+		WasmClientState::<FakeInner, FakeInner, FakeInner>::decode_vec(&any.value)
+			.map_err(|e| invalid(&e))?;
+	let inner = Any::decode(&*wasm_client_state.data).map_err(|e| invalid(&e))?;
+	check_type_url("client state", &inner.type_url, GRANDPA_CLIENT_STATE_TYPE_URL)?;
+	let client_state = Context::<H>::decode_client_state(&data).map_err(|e| invalid(&e))?;
+	client_state.verify_new_client_prefix().map_err(|e| invalid(&e))?;
+
+	let height = wasm_client_state.latest_height;
+	let data = ReadonlyConsensusStates::new(storage)
+		.get(height)
+		.ok_or_else(|| invalid(&format!("consensus state at height {height} is not stored")))?;
+	let any = Any::decode(&*data).map_err(|e| invalid(&e))?;
+	let wasm_consensus_state =
+		WasmConsensusState::<FakeInner>::decode_vec(&any.value).map_err(|e| invalid(&e))?;
+	let inner = Any::decode(&*wasm_consensus_state.data).map_err(|e| invalid(&e))?;
+	check_type_url("consensus state", &inner.type_url, GRANDPA_CONSENSUS_STATE_TYPE_URL)?;
+	Context::<H>::decode_consensus_state(&data).map_err(|e| invalid(&e))?;
+	Ok(())
+}
+
+fn check_type_url(kind: &str, type_url: &str, expected: &str) -> Result<(), ContractError> {
+	if type_url != expected {
+		return Err(ContractError::InvalidInitialState(format!(
+			"unexpected {kind} type {type_url}, expected {expected}"
+		)))
+	}
+	Ok(())
+}
+
+/// Rewrites the storage from the layout of contract version 0.1.0 to the current one:
+/// - the processed heights were stored with the host block height as their revision number and a
+///   zero revision height, they're now stored with the revision number of the host chain id and the
+///   block height as their revision height,
+/// - the client and consensus states are decoded and encoded again, so that the migration fails if
+///   the current version can't read any of them.
+pub fn migrate_from_legacy_layout<H: HostFunctions<Header = RelayChainHeader>>(
+	ctx: &mut Context<H>,
+) -> Result<(), ContractError> {
+	let revision_number = ChainId::chain_version(&ctx.env.block.chain_id);
+	let processed_heights = CLIENT_UPDATE_HEIGHT
+		.range(ctx.storage(), None, None, Order::Ascending)
+		.collect::<StdResult<Vec<_>>>()?;
+	for (key, processed_height) in processed_heights {
+		let processed_height = Height::from_str(&String::from_utf8(processed_height)?)
+			.map_err(|e| ContractError::Grandpa(format!("invalid processed height: {e}")))?;
+		if processed_height.revision_height != 0 {
+			continue
+		}
+		let processed_height = Height::new(revision_number, processed_height.revision_number);
+		CLIENT_UPDATE_HEIGHT.save(
+			ctx.storage_mut(),
+			key,
+			&processed_height.to_string().into_bytes(),
+		)?;
+	}
+
+	if let Some(data) = ReadonlyClientStates::new(ctx.storage()).get() {
+		let client_state = Context::<H>::decode_client_state(&data)
+			.map_err(|e| ContractError::Grandpa(format!("error decoding client state: {e}")))?;
+		let encoded = Context::<H>::encode_client_state(client_state, data)
+			.map_err(|e| ContractError::Grandpa(format!("error encoding client state: {e}")))?;
+		ClientStates::new(ctx.storage_mut()).insert(encoded);
+	}
+	for (key, value) in ReadonlyConsensusStates::new(ctx.storage()).entries() {
+		let consensus_state = Context::<H>::decode_consensus_state(&value)
+			.map_err(|e| ContractError::Grandpa(format!("error decoding consensus state: {e}")))?;
+		let encoded = Context::<H>::encode_consensus_state(consensus_state);
+		ctx.storage_mut().set(&key, &encoded);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::contract::HostFunctions;
+	use cosmwasm_std::testing::{mock_dependencies, mock_env};
+	use ibc::{core::ics02_client::context::ClientKeeper, timestamp::Timestamp};
+	use ics10_grandpa::consensus_state::ConsensusState;
+	use std::str::FromStr;
+
+	#[test]
+	fn test_prune_expired_consensus_states() {
+		let mut deps = mock_dependencies();
+		let mut env = mock_env();
+		let client_id = ClientId::from_str("08-wasm-0").unwrap();
+		let client_state = ClientState::<HostFunctions> {
+			para_id: 2000,
+			latest_para_height: 103,
+			..Default::default()
+		};
+		let trusting_period = client_state.trusting_period().as_nanos() as u64;
+		let now = env.block.time.nanos();
+		env.block.time = env.block.time.plus_nanos(trusting_period);
+		let mut ctx = Context::<HostFunctions>::new(deps.as_mut(), env);
+
+		// the first two consensus states are older than the trusting period
+		for (height, age) in [(100, 2), (101, 1), (102, 0), (103, 0)] {
+			let timestamp = Timestamp::from_nanoseconds(now - age).unwrap();
+			let consensus_state =
+				ConsensusState::new(vec![0; 32], timestamp.into_tm_time().unwrap());
+			ctx.store_consensus_state(
+				client_id.clone(),
+				Height::new(2000, height),
+				consensus_state,
+			)
+			.unwrap();
+		}
+
+		prune_expired_consensus_states(&mut ctx, &client_id, &client_state).unwrap();
+		assert_eq!(
+			ReadonlyConsensusStates::new(ctx.storage()).heights(),
+			vec![Height::new(2000, 102), Height::new(2000, 103)]
+		);
+	}
+
+	#[test]
+	fn test_prune_excess_consensus_states() {
+		let mut deps = mock_dependencies();
+		let env = mock_env();
+		let client_id = ClientId::from_str("08-wasm-0").unwrap();
+		let max_consensus_states = 3;
+		let mut ctx = Context::<HostFunctions>::new(deps.as_mut(), env);
+		let (host_timestamp, host_height) = (ctx.host_timestamp(), ctx.host_height());
+
+		let heights = (100..100 + max_consensus_states as u64 + 2)
+			.map(|height| Height::new(2000, height))
+			.collect::<Vec<_>>();
+		for height in &heights {
+			let consensus_state =
+				ConsensusState::new(vec![0; 32], host_timestamp.into_tm_time().unwrap());
+			ctx.store_consensus_state(client_id.clone(), *height, consensus_state).unwrap();
+			ctx.store_update_time(client_id.clone(), *height, host_timestamp).unwrap();
+			ctx.store_update_height(client_id.clone(), *height, host_height).unwrap();
+		}
+
+		let latest_height = *heights.last().unwrap();
+		prune_excess_consensus_states(&mut ctx, &client_id, latest_height, max_consensus_states);
+		assert_eq!(ReadonlyConsensusStates::new(ctx.storage()).heights(), heights[2..]);
+		for (i, height) in heights.iter().enumerate() {
+			let key = (client_id.as_bytes().to_owned(), height.to_string().into_bytes());
+			let kept = i >= 2;
+			assert_eq!(CLIENT_UPDATE_TIME.has(ctx.storage(), key.clone()), kept);
+			assert_eq!(CLIENT_UPDATE_HEIGHT.has(ctx.storage(), key), kept);
+		}
+
+		// the consensus state at the latest height is kept even if it's the oldest one
+		let latest_height = heights[2];
+		let consensus_state =
+			ConsensusState::new(vec![0; 32], host_timestamp.into_tm_time().unwrap());
+		ctx.store_consensus_state(client_id.clone(), Height::new(2000, 200), consensus_state)
+			.unwrap();
+		prune_excess_consensus_states(&mut ctx, &client_id, latest_height, max_consensus_states);
+		assert_eq!(
+			ReadonlyConsensusStates::new(ctx.storage()).heights(),
+			vec![heights[2], heights[4], Height::new(2000, 200)]
+		);
+	}
+
+	#[test]
+	fn test_verify_delay_passed() {
+		let mut deps = mock_dependencies();
+		let mut env = mock_env();
+		let client_id = ClientId::from_str("08-wasm-0").unwrap();
+		let height = Height::new(2000, 100);
+		let (delay_time, delay_blocks) = (1_000_000_000, 5);
+
+		let mut ctx = Context::<HostFunctions>::new(deps.as_mut(), env.clone());
+		let (host_timestamp, host_height) = (ctx.host_timestamp(), ctx.host_height());
+		ctx.store_update_time(client_id.clone(), height, host_timestamp).unwrap();
+		ctx.store_update_height(client_id.clone(), height, host_height).unwrap();
+
+		// zero delays don't need the processed time and height
+		verify_delay_passed(&ctx, &client_id, Height::new(2000, 101), 0, 0).unwrap();
+
+		// neither period has elapsed yet
+		verify_delay_passed(&ctx, &client_id, height, delay_time, delay_blocks).unwrap_err();
+
+		// only the delay time period has elapsed
+		env.block.time = env.block.time.plus_nanos(delay_time);
+		env.block.height += delay_blocks - 1;
+		let ctx = Context::<HostFunctions>::new(deps.as_mut(), env.clone());
+		verify_delay_passed(&ctx, &client_id, height, delay_time, delay_blocks).unwrap_err();
+
+		// both periods have elapsed
+		env.block.height += 1;
+		let ctx = Context::<HostFunctions>::new(deps.as_mut(), env);
+		verify_delay_passed(&ctx, &client_id, height, delay_time, delay_blocks).unwrap();
+	}
+}
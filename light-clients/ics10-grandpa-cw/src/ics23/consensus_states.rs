@@ -14,7 +14,7 @@
 // limitations under the License.
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Storage;
+use cosmwasm_std::{Order, Storage};
 use ibc::{
 	core::{
 		ics02_client::{
@@ -38,7 +38,7 @@ use ibc::{
 	Height,
 };
 use ibc_proto::google::protobuf::Any;
-use std::{convert::Infallible, time::Duration};
+use std::{convert::Infallible, str::FromStr, time::Duration};
 
 /// client_id, height => consensus_state
 /// trie key path: "clients/{client_id}/consensusStates/{height}"
@@ -87,6 +87,13 @@ impl<'a> ConsensusStates<'a> {
 
 		self.0.set(&full_key, &consensus_state);
 	}
+
+	pub fn remove(&mut self, height: Height) {
+		let (consensus_state_key_1, consensus_state_key_2) = Self::consensus_state_key(height);
+		let full_key =
+			[consensus_state_key_1.as_slice(), consensus_state_key_2.as_slice()].concat();
+		self.0.remove(&full_key);
+	}
 }
 
 /// client_id, height => consensus_state
@@ -114,6 +121,44 @@ impl<'a> ReadonlyConsensusStates<'a> {
 			[prefix, consensus_state_key_1.as_slice(), consensus_state_key_2.as_slice()].concat();
 		self.0.get(&full_key)
 	}
+
+	/// Returns all the stored `(key, consensus_state)` pairs ordered by key.
+	pub fn entries(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+		let (start, end) = Self::key_range();
+		self.0.range(Some(&start), Some(&end), Order::Ascending).collect()
+	}
+
+	/// Returns the keys of all the stored consensus states ordered by key, without loading the
+	/// consensus states themselves.
+	pub fn keys(&self) -> Vec<Vec<u8>> {
+		let (start, end) = Self::key_range();
+		self.0.range_keys(Some(&start), Some(&end), Order::Ascending).collect()
+	}
+
+	/// Returns the heights of all the stored consensus states in ascending order.
+	pub fn heights(&self) -> Vec<Height> {
+		let prefix_len = ConsensusStates::consensus_state_client_key().len();
+		let mut heights = self
+			.keys()
+			.into_iter()
+			.filter_map(|key| {
+				let height = std::str::from_utf8(&key[prefix_len..]).ok()?;
+				Height::from_str(height).ok()
+			})
+			.collect::<Vec<_>>();
+		// keys are ordered lexicographically, which isn't the order of the heights
+		heights.sort();
+		heights
+	}
+
+	/// The range of keys holding consensus states.
+	fn key_range() -> (Vec<u8>, Vec<u8>) {
+		let start = ConsensusStates::consensus_state_client_key();
+		// "consensusStates0" is the first key past the "consensusStates/" prefix
+		let mut end = start.clone();
+		*end.last_mut().expect("key is not empty") += 1;
+		(start, end)
+	}
 }
 
 #[derive(Eq, Default)]
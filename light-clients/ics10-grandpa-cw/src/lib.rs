@@ -27,7 +27,7 @@ pub mod ics23;
 mod macros;
 pub mod msg;
 pub mod state;
-mod types;
+pub mod types;
 
 pub use crate::error::ContractError;
 
@@ -15,11 +15,11 @@
 
 use crate::{ics23::FakeInner, Bytes, ContractError};
 use core::str::FromStr;
-use cosmwasm_schema::cw_serde;
+use cosmwasm_schema::{cw_serde, QueryResponses};
 use ibc::{
 	core::{
 		ics23_commitment::commitment::{CommitmentPrefix, CommitmentProofBytes},
-		ics24_host::Path,
+		ics24_host::{path::PathError, Path},
 	},
 	protobuf::Protobuf,
 	Height,
@@ -55,20 +55,102 @@ pub struct GenesisMetadata {
 	pub value: Vec<u8>,
 }
 
+/// A stored consensus state, as returned by the `IterateConsensusStates` query.
+#[cw_serde]
+pub struct ConsensusStateEntry {
+	pub height: HeightRaw,
+	pub consensus_state: Vec<u8>,
+}
+
 #[cw_serde]
 pub struct QueryResponse {
 	pub status: String,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub genesis_metadata: Option<Vec<GenesisMetadata>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub heights: Option<Vec<HeightRaw>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub timestamp: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub consensus_states: Option<Vec<ConsensusStateEntry>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub client_type: Option<String>,
+	/// Height at which the client was frozen, only set in the response to a frozen client's status
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub frozen_height: Option<HeightRaw>,
 }
 
 impl QueryResponse {
-	pub fn status(status: String) -> Self {
-		Self { status, genesis_metadata: None }
+	pub fn status(status: String, frozen_height: Option<HeightRaw>) -> Self {
+		Self {
+			status,
+			genesis_metadata: None,
+			heights: None,
+			timestamp: None,
+			consensus_states: None,
+			client_type: None,
+			frozen_height,
+		}
 	}
 
 	pub fn genesis_metadata(genesis_metadata: Option<Vec<GenesisMetadata>>) -> Self {
-		Self { status: "".to_string(), genesis_metadata }
+		Self {
+			status: "".to_string(),
+			genesis_metadata,
+			heights: None,
+			timestamp: None,
+			consensus_states: None,
+			client_type: None,
+			frozen_height: None,
+		}
+	}
+
+	pub fn heights(heights: Vec<HeightRaw>) -> Self {
+		Self {
+			status: "".to_string(),
+			genesis_metadata: None,
+			heights: Some(heights),
+			timestamp: None,
+			consensus_states: None,
+			client_type: None,
+			frozen_height: None,
+		}
+	}
+
+	pub fn timestamp(timestamp: u64) -> Self {
+		Self {
+			status: "".to_string(),
+			genesis_metadata: None,
+			heights: None,
+			timestamp: Some(timestamp),
+			consensus_states: None,
+			client_type: None,
+			frozen_height: None,
+		}
+	}
+
+	pub fn consensus_states(consensus_states: Vec<ConsensusStateEntry>) -> Self {
+		Self {
+			status: "".to_string(),
+			genesis_metadata: None,
+			heights: None,
+			timestamp: None,
+			consensus_states: Some(consensus_states),
+			client_type: None,
+			frozen_height: None,
+		}
+	}
+
+	pub fn client_type(client_type: String) -> Self {
+		Self {
+			status: "".to_string(),
+			genesis_metadata: None,
+			heights: None,
+			timestamp: None,
+			consensus_states: None,
+			client_type: Some(client_type),
+			frozen_height: None,
+		}
 	}
 }
 
@@ -79,15 +161,35 @@ pub struct ContractResult {
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub data: Option<Vec<u8>>,
 	pub found_misbehaviour: bool,
+	/// Heights of the consensus states stored by a state update.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub heights: Option<Vec<HeightRaw>>,
+	/// Results of the items of a verification batch, in the order they were sent.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub results: Option<Vec<ContractResult>>,
 }
 
 impl ContractResult {
 	pub fn success() -> Self {
-		Self { is_valid: true, error_msg: "".to_string(), data: None, found_misbehaviour: false }
+		Self {
+			is_valid: true,
+			error_msg: "".to_string(),
+			data: None,
+			found_misbehaviour: false,
+			heights: None,
+			results: None,
+		}
 	}
 
 	pub fn error(msg: String) -> Self {
-		Self { is_valid: false, error_msg: msg, data: None, found_misbehaviour: false }
+		Self {
+			is_valid: false,
+			error_msg: msg,
+			data: None,
+			found_misbehaviour: false,
+			heights: None,
+			results: None,
+		}
 	}
 
 	pub fn misbehaviour(mut self, found: bool) -> Self {
@@ -99,6 +201,27 @@ impl ContractResult {
 		self.data = Some(data);
 		self
 	}
+
+	pub fn heights(mut self, heights: Vec<HeightRaw>) -> Self {
+		self.heights = Some(heights);
+		self
+	}
+
+	/// Builds the result of a verification batch, which is only valid if all of its items are.
+	/// The error message names the first failing item by its index.
+	pub fn batch(results: Vec<Result<(), ContractError>>) -> Self {
+		let first_error = results.iter().enumerate().find_map(|(index, result)| {
+			result.as_ref().err().map(|e| format!("item {index}: {e}"))
+		});
+		let results = results
+			.into_iter()
+			.map(|result| match result {
+				Ok(()) => Self::success(),
+				Err(e) => Self::error(e.to_string()),
+			})
+			.collect();
+		Self { results: Some(results), ..first_error.map_or_else(Self::success, Self::error) }
+	}
 }
 
 #[cw_serde]
@@ -110,15 +233,27 @@ pub struct ClientStateCallResponse {
 }
 
 #[cw_serde]
-pub struct MigrateMsg {}
+pub enum MigrateMsg {
+	/// Migrates the contract storage from the layout used by the given contract version.
+	Migrate { from_version: String },
+}
 
 #[cw_serde]
-pub struct InstantiateMsg {}
+#[derive(Default)]
+pub struct InstantiateMsg {
+	/// The maximum number of consensus states to keep, the oldest ones being removed on state
+	/// updates. Consensus states are only removed once expired if not set.
+	pub max_consensus_states: Option<u32>,
+}
 
 #[cw_serde]
 pub enum ExecuteMsg {
 	VerifyMembership(VerifyMembershipMsgRaw),
 	VerifyNonMembership(VerifyNonMembershipMsgRaw),
+	/// Verifies several memberships at once, loading the client state only once.
+	VerifyMembershipBatch(Vec<VerifyMembershipMsgRaw>),
+	/// Verifies several non-memberships at once, loading the client state only once.
+	VerifyNonMembershipBatch(Vec<VerifyNonMembershipMsgRaw>),
 	VerifyClientMessage(VerifyClientMessageRaw),
 	CheckForMisbehaviour(CheckForMisbehaviourMsgRaw),
 	UpdateStateOnMisbehaviour(UpdateStateOnMisbehaviourMsgRaw),
@@ -128,11 +263,20 @@ pub enum ExecuteMsg {
 }
 
 #[cw_serde]
+#[derive(QueryResponses)]
 pub enum QueryMsg {
+	#[returns(QueryResponse)]
 	ClientTypeMsg(ClientTypeMsg),
+	#[returns(QueryResponse)]
 	GetLatestHeightsMsg(GetLatestHeightsMsg),
+	#[returns(QueryResponse)]
 	ExportMetadata(ExportMetadataMsg),
+	#[returns(QueryResponse)]
 	Status(StatusMsg),
+	#[returns(QueryResponse)]
+	TimestampAtHeight(TimestampAtHeightMsg),
+	#[returns(QueryResponse)]
+	IterateConsensusStates(IterateConsensusStatesMsg),
 }
 
 #[cw_serde]
@@ -147,11 +291,55 @@ pub struct StatusMsg {}
 #[cw_serde]
 pub struct ExportMetadataMsg {}
 
+#[cw_serde]
+pub struct TimestampAtHeightMsg {
+	pub height: HeightRaw,
+}
+
+/// Requests up to `limit` stored consensus states in ascending height order, starting at
+/// `start_height` (included) or at the lowest height if none is given.
+#[cw_serde]
+pub struct IterateConsensusStatesMsg {
+	pub start_height: Option<HeightRaw>,
+	pub limit: u32,
+}
+
 #[cw_serde]
 pub struct MerklePath {
 	pub key_path: Vec<String>,
 }
 
+impl MerklePath {
+	/// Splits the path into the commitment prefix (the first segment) and the IBC path made of
+	/// the remaining segments. The remaining segments are joined with `/`, so both a single
+	/// segment holding the whole path (e.g. `["ibc", "connections/connection-0"]`, as sent by
+	/// ibc-go) and ICS23 multi-segment paths (e.g. `["ibc", "connections", "connection-0"]`) are
+	/// supported. Paths without a prefix segment or with empty segments are rejected.
+	pub fn into_prefix_and_path(mut self) -> Result<(CommitmentPrefix, Path), ContractError> {
+		if self.key_path.len() < 2 {
+			return Err(ContractError::InvalidMerklePath(self.key_path.len()))
+		}
+		let prefix = CommitmentPrefix::try_from(self.key_path.remove(0).into_bytes())?;
+		let path_str = self.key_path.join("/");
+		let path = if path_str.split('/').any(str::is_empty) {
+			Err(PathError::parse_failure(path_str.clone()))
+		} else {
+			Path::from_str(&path_str)
+		}
+		.map_err(|source| ContractError::InvalidPath { value: path_str, source })?;
+		Ok((prefix, path))
+	}
+}
+
+/// Converts the height of a proof, which can't be zero.
+fn proof_height(raw: HeightRaw) -> Result<Height, ContractError> {
+	let height = Height::from(raw);
+	if height.is_zero() {
+		return Err(ContractError::InvalidHeight(format!("proof height {height} is zero")))
+	}
+	Ok(height)
+}
+
 #[cw_serde]
 pub struct VerifyMembershipMsgRaw {
 	#[schemars(with = "String")]
@@ -172,23 +360,26 @@ pub struct VerifyMembershipMsg {
 	pub path: Path,
 	pub value: Vec<u8>,
 	pub height: Height,
+	pub delay_block_period: u64,
+	pub delay_time_period: u64,
 }
 
 impl TryFrom<VerifyMembershipMsgRaw> for VerifyMembershipMsg {
 	type Error = ContractError;
 
-	fn try_from(mut raw: VerifyMembershipMsgRaw) -> Result<Self, Self::Error> {
-		let proof = CommitmentProofBytes::try_from(raw.proof)?;
-		let prefix = raw.path.key_path.remove(0).into_bytes();
-		let path_str = raw.path.key_path.join("");
-		let path = Path::from_str(&path_str)?;
-		let height = Height::from(raw.height);
+	fn try_from(raw: VerifyMembershipMsgRaw) -> Result<Self, Self::Error> {
+		let proof = CommitmentProofBytes::try_from(raw.proof)
+			.map_err(|e| ContractError::InvalidProof(e.to_string()))?;
+		let (prefix, path) = raw.path.into_prefix_and_path()?;
+		let height = proof_height(raw.height)?;
 		Ok(Self {
 			proof,
 			path,
 			value: raw.value,
 			height,
-			prefix: CommitmentPrefix::try_from(prefix)?,
+			prefix,
+			delay_block_period: raw.delay_block_period,
+			delay_time_period: raw.delay_time_period,
 		})
 	}
 }
@@ -209,18 +400,26 @@ pub struct VerifyNonMembershipMsg {
 	pub proof: CommitmentProofBytes,
 	pub path: Path,
 	pub height: Height,
+	pub delay_block_period: u64,
+	pub delay_time_period: u64,
 }
 
 impl TryFrom<VerifyNonMembershipMsgRaw> for VerifyNonMembershipMsg {
 	type Error = ContractError;
 
-	fn try_from(mut raw: VerifyNonMembershipMsgRaw) -> Result<Self, Self::Error> {
-		let proof = CommitmentProofBytes::try_from(raw.proof)?;
-		let prefix = raw.path.key_path.remove(0).into_bytes();
-		let path_str = raw.path.key_path.join("");
-		let path = Path::from_str(&path_str)?;
-		let height = Height::from(raw.height);
-		Ok(Self { proof, path, height, prefix: CommitmentPrefix::try_from(prefix)? })
+	fn try_from(raw: VerifyNonMembershipMsgRaw) -> Result<Self, Self::Error> {
+		let proof = CommitmentProofBytes::try_from(raw.proof)
+			.map_err(|e| ContractError::InvalidProof(e.to_string()))?;
+		let (prefix, path) = raw.path.into_prefix_and_path()?;
+		let height = proof_height(raw.height)?;
+		Ok(Self {
+			proof,
+			path,
+			height,
+			prefix,
+			delay_block_period: raw.delay_block_period,
+			delay_time_period: raw.delay_time_period,
+		})
 	}
 }
 
@@ -257,14 +456,24 @@ impl TryFrom<VerifyClientMessageRaw> for VerifyClientMessage {
 
 impl VerifyClientMessage {
 	fn decode_client_message(raw: ClientMessageRaw) -> Result<ClientMessage, ContractError> {
+		let invalid = |kind: &str, e: &dyn core::fmt::Display| {
+			ContractError::InvalidClientMessage(format!("{kind}: {e}"))
+		};
 		let client_message = match raw {
 			ClientMessageRaw::Header(header) => {
-				let any = Any::decode(&mut header.data.as_slice())?;
-				ClientMessage::Header(Header::decode_vec(&any.value)?)
+				let any = Any::decode(&mut header.data.as_slice())
+					.map_err(|e| invalid("header Any", &e))?;
+				ClientMessage::Header(
+					Header::decode_vec(&any.value).map_err(|e| invalid("header", &e))?,
+				)
 			},
 			ClientMessageRaw::Misbehaviour(misbehaviour) => {
-				let any = Any::decode(&mut misbehaviour.data.as_slice())?;
-				ClientMessage::Misbehaviour(Misbehaviour::decode_vec(&any.value)?)
+				let any = Any::decode(&mut misbehaviour.data.as_slice())
+					.map_err(|e| invalid("misbehaviour Any", &e))?;
+				ClientMessage::Misbehaviour(
+					Misbehaviour::decode_vec(&any.value)
+						.map_err(|e| invalid("misbehaviour", &e))?,
+				)
 			},
 		};
 		Ok(client_message)
@@ -325,6 +534,8 @@ impl TryFrom<UpdateStateMsgRaw> for UpdateStateMsg {
 	}
 }
 
+/// The subject and substitute client states are read from the contract storage under the
+/// `SUBJECT_PREFIX` and `SUBSTITUTE_PREFIX` prefixes, so the message itself carries no data.
 #[cw_serde]
 pub struct CheckSubstituteAndUpdateStateMsgRaw {}
 
@@ -375,3 +586,102 @@ impl<H: Clone> TryFrom<VerifyUpgradeAndUpdateStateMsgRaw> for VerifyUpgradeAndUp
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn merkle_path(segments: &[&str]) -> MerklePath {
+		MerklePath { key_path: segments.iter().map(|s| s.to_string()).collect() }
+	}
+
+	#[test]
+	fn test_merkle_path_into_prefix_and_path() {
+		let expected = Path::from_str("clients/07-tendermint-0/clientState").unwrap();
+
+		// ibc-go sends the whole IBC path as a single segment after the prefix
+		let (prefix, path) = merkle_path(&["ibc", "clients/07-tendermint-0/clientState"])
+			.into_prefix_and_path()
+			.unwrap();
+		assert_eq!(prefix.as_bytes(), b"ibc");
+		assert_eq!(path, expected);
+
+		let (prefix, path) = merkle_path(&["ibc", "clients", "07-tendermint-0", "clientState"])
+			.into_prefix_and_path()
+			.unwrap();
+		assert_eq!(prefix.as_bytes(), b"ibc");
+		assert_eq!(path, expected);
+	}
+
+	#[test]
+	fn test_merkle_path_without_prefix_is_rejected() {
+		for segments in [&[][..], &["clients/07-tendermint-0/clientState"][..]] {
+			let err = merkle_path(segments).into_prefix_and_path().unwrap_err();
+			assert!(
+				matches!(err, ContractError::InvalidMerklePath(len) if len == segments.len()),
+				"{err}"
+			);
+		}
+	}
+
+	#[test]
+	fn test_merkle_path_with_empty_segments_is_rejected() {
+		for segments in [
+			&["ibc", ""][..],
+			&["ibc", "clients/07-tendermint-0/clientState/"][..],
+			&["ibc", "clients", "07-tendermint-0", "clientState", ""][..],
+			&["ibc", "", "clients/07-tendermint-0/clientState"][..],
+		] {
+			let err = merkle_path(segments).into_prefix_and_path().unwrap_err();
+			assert!(matches!(err, ContractError::InvalidPath { .. }), "{err}");
+		}
+	}
+
+	fn verify_membership_msg(proof: Vec<u8>, path: &[&str]) -> VerifyMembershipMsgRaw {
+		VerifyMembershipMsgRaw {
+			proof,
+			path: merkle_path(path),
+			value: vec![1],
+			height: Height::new(2000, 10).into(),
+			delay_block_period: 0,
+			delay_time_period: 0,
+		}
+	}
+
+	#[test]
+	fn test_conversion_errors() {
+		let path = ["ibc", "clients/07-tendermint-0/clientState"];
+		VerifyMembershipMsg::try_from(verify_membership_msg(vec![1], &path)).unwrap();
+
+		let err = VerifyMembershipMsg::try_from(verify_membership_msg(vec![], &path))
+			.err()
+			.unwrap();
+		assert!(matches!(err, ContractError::InvalidProof(_)), "{err}");
+		assert!(err.to_string().starts_with("Invalid proof"), "{err}");
+
+		let bad_path = ["ibc", "clients//clientState"];
+		let err = VerifyMembershipMsg::try_from(verify_membership_msg(vec![1], &bad_path))
+			.err()
+			.unwrap();
+		assert!(matches!(err, ContractError::InvalidPath { .. }), "{err}");
+		assert!(err.to_string().starts_with("Invalid path \"clients//clientState\""), "{err}");
+
+		let mut raw = verify_membership_msg(vec![1], &path);
+		raw.height = Height::new(0, 0).into();
+		let err = VerifyMembershipMsg::try_from(raw).err().unwrap();
+		assert!(matches!(err, ContractError::InvalidHeight(_)), "{err}");
+
+		let mut data =
+			Any { type_url: "/ibc.lightclients.grandpa.v1.Header".to_string(), value: vec![1; 8] }
+				.encode_to_vec();
+		data.truncate(data.len() - 1);
+		let header = WasmHeader { inner: Box::new(FakeInner), data, height: Height::new(2000, 10) };
+		let err = VerifyClientMessage::try_from(VerifyClientMessageRaw {
+			client_message: ClientMessageRaw::Header(header),
+		})
+		.err()
+		.unwrap();
+		assert!(matches!(err, ContractError::InvalidClientMessage(_)), "{err}");
+		assert!(err.to_string().starts_with("Invalid client message: header Any"), "{err}");
+	}
+}
@@ -13,7 +13,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::ics23::FakeInner;
+use crate::{
+	contract::{CLIENT_UPDATE_HEIGHT, CLIENT_UPDATE_TIME},
+	ics23::{FakeInner, ReadonlyConsensusStates},
+	msg::{ConsensusStateEntry, GenesisMetadata},
+};
 use cosmwasm_std::Deps;
 use ibc::{
 	core::{ics02_client::error::Error, ics24_host::identifier::ClientId},
@@ -62,3 +66,81 @@ pub fn get_consensus_state(
 pub fn get_consensus_state_key(height: Height) -> Vec<u8> {
 	["consensusStates/".to_string().into_bytes(), format!("{height}").into_bytes()].concat()
 }
+
+/// Key under which ibc-go stores the iteration key of the consensus state at `height`: the
+/// "iterateConsensusStates" prefix followed by the big endian revision number and height.
+pub fn get_iteration_key(height: Height) -> Vec<u8> {
+	[
+		b"iterateConsensusStates".as_slice(),
+		&height.revision_number.to_be_bytes(),
+		&height.revision_height.to_be_bytes(),
+	]
+	.concat()
+}
+
+/// Exports the consensus states' metadata following ibc-go's `GenesisMetadata` convention, i.e.
+/// for every consensus state:
+/// - `consensusStates/{height}/processedTime` => the big endian host time it was stored at,
+/// - `consensusStates/{height}/processedHeight` => the host height it was stored at,
+/// - the iteration key of `height` => `consensusStates/{height}`.
+///
+/// The pairs are ordered by key, the order ibc-go's store iterators yield them in.
+pub fn get_genesis_metadata(deps: Deps, client_id: &ClientId) -> Vec<GenesisMetadata> {
+	let mut metadata = vec![];
+	for height in ReadonlyConsensusStates::new(deps.storage).heights() {
+		let key = (client_id.as_bytes().to_owned(), height.to_string().into_bytes());
+		if let Ok(processed_time) = CLIENT_UPDATE_TIME.load(deps.storage, key.clone()) {
+			metadata.push(GenesisMetadata {
+				key: format!("consensusStates/{height}/processedTime").into_bytes(),
+				value: processed_time.to_be_bytes().to_vec(),
+			});
+		}
+		if let Ok(processed_height) = CLIENT_UPDATE_HEIGHT.load(deps.storage, key) {
+			metadata.push(GenesisMetadata {
+				key: format!("consensusStates/{height}/processedHeight").into_bytes(),
+				value: processed_height,
+			});
+		}
+		metadata.push(GenesisMetadata {
+			key: get_iteration_key(height),
+			value: get_consensus_state_key(height),
+		});
+	}
+	metadata.sort_by(|a, b| a.key.cmp(&b.key));
+	metadata
+}
+
+/// Returns the client's latest height and the highest height a consensus state is stored at,
+/// latest first and without duplicates.
+pub fn get_latest_heights<H: Clone>(deps: Deps) -> Vec<Height> {
+	let mut heights = vec![];
+	if let Ok(client_state) = get_client_state::<H>(deps) {
+		heights.push(client_state.latest_height());
+	}
+	if let Some(highest) = ReadonlyConsensusStates::new(deps.storage).heights().pop() {
+		heights.push(highest);
+	}
+	heights.sort_by(|a, b| b.cmp(a));
+	heights.dedup();
+	heights
+}
+
+/// Returns up to `limit` stored consensus states with their heights in ascending height order,
+/// starting at `start_height` if given.
+pub fn get_consensus_states_page(
+	deps: Deps,
+	start_height: Option<Height>,
+	limit: usize,
+) -> Vec<ConsensusStateEntry> {
+	let consensus_states = ReadonlyConsensusStates::new(deps.storage);
+	consensus_states
+		.heights()
+		.into_iter()
+		.filter(|height| start_height.map_or(true, |start| *height >= start))
+		.take(limit)
+		.filter_map(|height| {
+			let consensus_state = consensus_states.get(height)?;
+			Some(ConsensusStateEntry { height: height.into(), consensus_state })
+		})
+		.collect()
+}
@@ -24,6 +24,13 @@ pub struct Height {
 	pub revision_height: u64,
 }
 
+/// Name and version of the contract, stored the same way as cw2 does.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct ContractVersion {
+	pub contract: String,
+	pub version: String,
+}
+
 impl From<Height> for ibc::Height {
 	fn from(value: Height) -> Self {
 		Self { revision_number: value.revision_number, revision_height: value.revision_height }
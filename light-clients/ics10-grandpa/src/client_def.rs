@@ -19,7 +19,12 @@ use ibc::core::ics02_client::{
 };
 
 use crate::client_message::{ClientMessage, RelayChainHeader};
-use alloc::{format, string::ToString, vec, vec::Vec};
+use alloc::{
+	format,
+	string::{String, ToString},
+	vec,
+	vec::Vec,
+};
 use codec::Decode;
 use core::marker::PhantomData;
 use finality_grandpa::Chain;
@@ -27,7 +32,7 @@ use grandpa_client_primitives::{
 	justification::{
 		find_forced_change, find_scheduled_change, AncestryChain, GrandpaJustification,
 	},
-	ParachainHeadersWithFinalityProof,
+	FinalityProof, ParachainHeadersWithFinalityProof,
 };
 use ibc::{
 	core::{
@@ -61,8 +66,8 @@ use sp_runtime::traits::Header;
 use sp_trie::StorageProof;
 use tendermint_proto::Protobuf;
 
-const CLIENT_STATE_UPGRADE_PATH: &[u8] = b"client-state-upgrade-path";
-const CONSENSUS_STATE_UPGRADE_PATH: &[u8] = b"consensus-state-upgrade-path";
+pub(crate) const CLIENT_STATE_UPGRADE_PATH: &[u8] = b"client-state-upgrade-path";
+pub(crate) const CONSENSUS_STATE_UPGRADE_PATH: &[u8] = b"consensus-state-upgrade-path";
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct GrandpaClient<T>(PhantomData<T>);
@@ -91,6 +96,21 @@ where
 					))
 					.into())
 				}
+				// the update must finalize a relay chain block above the latest known one.
+				let target_number = header
+					.finality_proof
+					.unknown_headers
+					.iter()
+					.map(|h| h.number)
+					.max()
+					.unwrap_or_default();
+				if target_number <= client_state.latest_relay_height {
+					return Err(Error::Custom(format!(
+						"Header doesn't advance the client: relay chain height {target_number} is not above {}",
+						client_state.latest_relay_height
+					))
+					.into())
+				}
 				let headers_with_finality_proof = ParachainHeadersWithFinalityProof {
 					finality_proof: header.finality_proof,
 					parachain_headers: header.parachain_headers,
@@ -319,8 +339,15 @@ where
 		client_state: Self::ClientState,
 		client_message: Self::ClientMessage,
 	) -> Result<bool, Ics02Error> {
-		if matches!(client_message, ClientMessage::Misbehaviour(_)) {
-			return Ok(true)
+		if let ClientMessage::Misbehaviour(misbehaviour) = &client_message {
+			// both finality proofs descend from the same known block, so they only conflict if
+			// neither of them finalizes the other one's target.
+			let first = &misbehaviour.first_finality_proof;
+			let second = &misbehaviour.second_finality_proof;
+			let finalizes = |proof: &FinalityProof<RelayChainHeader>, hash: H256| {
+				proof.unknown_headers.iter().any(|header| header.hash() == hash)
+			};
+			return Ok(!finalizes(first, second.block) && !finalizes(second, first.block))
 		}
 
 		// we also check that this update doesn't include competing consensus states for heights we
@@ -382,6 +409,19 @@ where
 			old_client_state.latest_para_height as u64,
 		);
 
+		if upgrade_client_state.latest_height() <= height {
+			Err(Error::Custom(format!(
+				"Upgraded client height {} must be greater than the current client height {height}",
+				upgrade_client_state.latest_height()
+			)))?
+		}
+
+		// the frozen height is the only client-chosen field, the upgraded client state committed
+		// by the chain must leave it empty.
+		if upgrade_client_state.frozen_height.is_some() {
+			Err(Error::Custom(format!("Upgraded client state must not be frozen")))?
+		}
+
 		let consenus_state = ctx.consensus_state(&client_id, height)?
 			.downcast::<Self::ConsensusState>()
 			.ok_or_else(|| Error::Custom(format!("Wrong consensus state type stored for Grandpa client with {client_id} at {height}")))?;
@@ -409,7 +449,12 @@ where
 			.map_err(|err| Error::Custom(format!("{err}")))?
 			.remove(CLIENT_STATE_UPGRADE_PATH)
 			.flatten()
-			.ok_or_else(|| Error::Custom(format!("Invalid proof for client state upgrade")))?;
+			.ok_or_else(|| {
+				Error::Custom(format!(
+					"No upgraded client state found at {}",
+					String::from_utf8_lossy(CLIENT_STATE_UPGRADE_PATH)
+				))
+			})?;
 
 			if value != encoded {
 				Err(Error::Custom(format!("Invalid proof for client state upgrade")))?
@@ -424,7 +469,7 @@ where
 				StorageProof::new(nodes)
 			};
 
-			let encoded = Ctx::AnyConsensusState::wrap(upgrade_client_state)
+			let encoded = Ctx::AnyConsensusState::wrap(upgrade_consensus_state)
 				.expect("AnyConsensusState is type-checked; qed")
 				.encode_to_vec()
 				.map_err(Ics02Error::encode)?;
@@ -437,10 +482,15 @@ where
 			.map_err(|err| Error::Custom(format!("{err}")))?
 			.remove(CONSENSUS_STATE_UPGRADE_PATH)
 			.flatten()
-			.ok_or_else(|| Error::Custom(format!("Invalid proof for client state upgrade")))?;
+			.ok_or_else(|| {
+				Error::Custom(format!(
+					"No upgraded consensus state found at {}",
+					String::from_utf8_lossy(CONSENSUS_STATE_UPGRADE_PATH)
+				))
+			})?;
 
 			if value != encoded {
-				Err(Error::Custom(format!("Invalid proof for client state upgrade")))?
+				Err(Error::Custom(format!("Invalid proof for consensus state upgrade")))?
 			}
 		}
 
@@ -484,6 +534,7 @@ where
 		expected_consensus_state: &Ctx::AnyConsensusState,
 	) -> Result<(), Ics02Error> {
 		client_state.verify_height(height)?;
+		client_state.verify_prefix(prefix)?;
 		let path = ClientConsensusStatePath {
 			client_id: client_id.clone(),
 			epoch: consensus_height.revision_number,
@@ -508,6 +559,7 @@ where
 		expected_connection_end: &ConnectionEnd,
 	) -> Result<(), Ics02Error> {
 		client_state.verify_height(height)?;
+		client_state.verify_prefix(prefix)?;
 		let path = ConnectionsPath(connection_id.clone());
 		let value = expected_connection_end.encode_vec().map_err(Ics02Error::encode)?;
 		verify_membership::<H::BlakeTwo256, _>(prefix, proof, root, path, value)
@@ -529,6 +581,7 @@ where
 		expected_channel_end: &ChannelEnd,
 	) -> Result<(), Ics02Error> {
 		client_state.verify_height(height)?;
+		client_state.verify_prefix(prefix)?;
 		let path = ChannelEndsPath(port_id.clone(), *channel_id);
 		let value = expected_channel_end.encode_vec().map_err(Ics02Error::encode)?;
 		verify_membership::<H::BlakeTwo256, _>(prefix, proof, root, path, value)
@@ -548,6 +601,7 @@ where
 		expected_client_state: &Ctx::AnyClientState,
 	) -> Result<(), Ics02Error> {
 		client_state.verify_height(height)?;
+		client_state.verify_prefix(prefix)?;
 		let path = ClientStatePath(client_id.clone());
 		let value = expected_client_state.encode_to_vec().map_err(Ics02Error::encode)?;
 		verify_membership::<H::BlakeTwo256, _>(prefix, proof, root, path, value)
@@ -570,6 +624,7 @@ where
 		commitment: PacketCommitment,
 	) -> Result<(), Ics02Error> {
 		client_state.verify_height(height)?;
+		client_state.verify_prefix(connection_end.counterparty().prefix())?;
 		verify_delay_passed::<H, _>(ctx, height, connection_end).map_err(Error::Anyhow)?;
 
 		let commitment_path =
@@ -601,6 +656,7 @@ where
 		ack: AcknowledgementCommitment,
 	) -> Result<(), Ics02Error> {
 		client_state.verify_height(height)?;
+		client_state.verify_prefix(connection_end.counterparty().prefix())?;
 		verify_delay_passed::<H, _>(ctx, height, connection_end).map_err(Error::Anyhow)?;
 
 		let ack_path = AcksPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
@@ -629,6 +685,7 @@ where
 		sequence: Sequence,
 	) -> Result<(), Ics02Error> {
 		client_state.verify_height(height)?;
+		client_state.verify_prefix(connection_end.counterparty().prefix())?;
 		verify_delay_passed::<H, _>(ctx, height, connection_end).map_err(Error::Anyhow)?;
 
 		let seq_bytes = codec::Encode::encode(&u64::from(sequence));
@@ -659,6 +716,7 @@ where
 		sequence: Sequence,
 	) -> Result<(), Ics02Error> {
 		client_state.verify_height(height)?;
+		client_state.verify_prefix(connection_end.counterparty().prefix())?;
 		verify_delay_passed::<H, _>(ctx, height, connection_end).map_err(Error::Anyhow)?;
 
 		let receipt_path =
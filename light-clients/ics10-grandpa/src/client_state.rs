@@ -28,13 +28,14 @@ use ibc::{
 			client_consensus::ConsensusState,
 			client_state::{ClientType, Status},
 		},
+		ics23_commitment::commitment::CommitmentPrefix,
 		ics24_host::identifier::{ChainId, ClientId},
 		ics26_routing::context::ReaderContext,
 	},
 	Height,
 };
 use ibc_proto::google::protobuf::Any;
-use light_client_common::RelayChain;
+use light_client_common::{RelayChain, RelayChainPeriods};
 use serde::{Deserialize, Serialize};
 use sp_consensus_grandpa::AuthorityList;
 use sp_core::{ed25519::Public, H256};
@@ -61,6 +62,13 @@ pub struct ClientState<H> {
 	pub current_set_id: u64,
 	/// authorities for the current round
 	pub current_authorities: AuthorityList,
+	/// Commitment prefix of the parachain's ibc store, proofs are only accepted under this
+	/// prefix. Empty for clients created before the prefix was recorded.
+	pub commitment_prefix: Vec<u8>,
+	/// Trusting period overriding the relay chain's default one, see [`RelayChainPeriods`].
+	pub custom_trusting_period: Option<Duration>,
+	/// Maximum number of consensus states kept by the client, unbounded if `None`.
+	pub max_consensus_states: Option<u32>,
 	/// phantom type.
 	pub _phantom: PhantomData<H>,
 }
@@ -102,6 +110,27 @@ impl<H: Clone> ClientState<H> {
 		}
 	}
 
+	/// Verify that the given commitment prefix is the one the client is bound to
+	pub fn verify_prefix(&self, prefix: &CommitmentPrefix) -> Result<(), Error> {
+		if self.commitment_prefix.is_empty() || self.commitment_prefix == prefix.as_bytes() {
+			return Ok(())
+		}
+
+		Err(Error::MismatchedCommitmentPrefix {
+			expected: self.commitment_prefix.clone(),
+			got: prefix.as_bytes().to_vec(),
+		})
+	}
+
+	/// Verify that a newly created client is bound to a commitment prefix. Only clients created
+	/// before the prefix was recorded may have an empty one.
+	pub fn verify_new_client_prefix(&self) -> Result<(), Error> {
+		if self.commitment_prefix.is_empty() {
+			return Err(Error::EmptyCommitmentPrefix)
+		}
+		Ok(())
+	}
+
 	pub fn to_any(&self) -> Any {
 		Any {
 			type_url: GRANDPA_CLIENT_STATE_TYPE_URL.to_string(),
@@ -140,10 +169,25 @@ impl<H> ClientState<H> {
 		self
 	}
 
+	/// Unbonding and trusting periods of the client. Falls back to the relay chain's defaults if
+	/// the custom trusting period isn't shorter than the unbonding period.
+	pub fn periods(&self) -> RelayChainPeriods {
+		RelayChainPeriods::new(self.relay_chain, self.custom_trusting_period)
+			.unwrap_or_else(|_| self.relay_chain.into())
+	}
+
+	pub fn unbonding_period(&self) -> Duration {
+		self.periods().unbonding_period()
+	}
+
+	pub fn trusting_period(&self) -> Duration {
+		self.periods().trusting_period()
+	}
+
 	/// Check if the state is expired when `elapsed` time has passed since the latest consensus
 	/// state timestamp
 	pub fn expired(&self, elapsed: Duration) -> bool {
-		elapsed > self.relay_chain.trusting_period()
+		elapsed > self.trusting_period()
 	}
 
 	pub fn with_frozen_height(self, h: Height) -> Result<Self, Error> {
@@ -242,7 +286,12 @@ impl<H> TryFrom<RawClientState> for ClientState<H> {
 			})
 			.collect::<Result<_, Error>>()?;
 
-		let relay_chain = RelayChain::from_i32(raw.relay_chain)?;
+		let relay_chain = RelayChain::from_raw(raw.relay_chain, raw.relay_chain_unbonding_period)?;
+		let custom_trusting_period = raw.custom_trusting_period.map(Duration::from_secs);
+		RelayChainPeriods::new(relay_chain, custom_trusting_period)?;
+		if raw.max_consensus_states == Some(0) {
+			Err(Error::Custom("max_consensus_states must be greater than zero".to_string()))?
+		}
 		if raw.latest_relay_hash.len() != 32 {
 			Err(anyhow!("Invalid ed25519 public key lenght: {}", raw.latest_relay_hash.len()))?
 		}
@@ -259,6 +308,9 @@ impl<H> TryFrom<RawClientState> for ClientState<H> {
 			current_authorities,
 			latest_relay_hash,
 			latest_relay_height: raw.latest_relay_height,
+			commitment_prefix: raw.commitment_prefix,
+			custom_trusting_period,
+			max_consensus_states: raw.max_consensus_states,
 			_phantom: Default::default(),
 		})
 	}
@@ -273,7 +325,8 @@ impl<H> From<ClientState<H>> for RawClientState {
 			frozen_height: client_state
 				.frozen_height
 				.map(|frozen_height| frozen_height.revision_height),
-			relay_chain: client_state.relay_chain as i32,
+			relay_chain: client_state.relay_chain.to_i32(),
+			relay_chain_unbonding_period: client_state.relay_chain.custom_unbonding_period(),
 			para_id: client_state.para_id,
 			latest_para_height: client_state.latest_para_height,
 			current_authorities: client_state
@@ -285,6 +338,11 @@ impl<H> From<ClientState<H>> for RawClientState {
 					weight,
 				})
 				.collect(),
+			commitment_prefix: client_state.commitment_prefix,
+			custom_trusting_period: client_state
+				.custom_trusting_period
+				.map(|trusting_period| trusting_period.as_secs()),
+			max_consensus_states: client_state.max_consensus_states,
 		}
 	}
 }
@@ -14,7 +14,7 @@
 // limitations under the License.
 
 use crate::client_state::ClientState;
-use alloc::{borrow::ToOwned, format, string::String};
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
 use ibc::{
 	core::{ics02_client, ics04_channel, ics24_host::error::ValidationError},
 	timestamp::{ParseTimestampError, TimestampOverflowError},
@@ -32,6 +32,14 @@ pub enum Error {
 	ProtoBuf(DecodeError),
 	GrandpaPrimitives(grandpa_client_primitives::error::Error),
 	Anyhow(anyhow::Error),
+	#[from(ignore)]
+	#[display(fmt = "Mismatched commitment prefix, expected: {:?}, got: {:?}", expected, got)]
+	MismatchedCommitmentPrefix {
+		expected: Vec<u8>,
+		got: Vec<u8>,
+	},
+	#[display(fmt = "Client state isn't bound to a commitment prefix")]
+	EmptyCommitmentPrefix,
 	Custom(String),
 }
 
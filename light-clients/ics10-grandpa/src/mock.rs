@@ -82,6 +82,17 @@ impl grandpa_client_primitives::HostFunctions for HostFunctionsManager {
 
 impl light_client_common::HostFunctions for HostFunctionsManager {
 	type BlakeTwo256 = BlakeTwo256;
+	type Keccak256 = sp_runtime::traits::Keccak256;
+	type Sha256 = light_client_common::Sha256Hasher;
+
+	fn secp256k1_ecdsa_recover_compressed(
+		msg: &[u8; 32],
+		signature: &[u8; 65],
+	) -> Result<[u8; 33], light_client_common::EcdsaRecoveryError> {
+		<beefy_prover::Crypto as light_client_common::HostFunctions>::secp256k1_ecdsa_recover_compressed(
+			msg, signature,
+		)
+	}
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, ClientDef)]
@@ -14,26 +14,29 @@
 // limitations under the License.
 
 use crate::{
-	client_message::{ClientMessage, Header, RelayChainHeader},
+	client_def::{GrandpaClient, CLIENT_STATE_UPGRADE_PATH, CONSENSUS_STATE_UPGRADE_PATH},
+	client_message::{ClientMessage, Header, Misbehaviour, RelayChainHeader},
 	client_state::ClientState,
 	consensus_state::ConsensusState,
 	mock::{
 		AnyClientMessage, AnyClientState, AnyConsensusState, HostFunctionsManager, MockClientTypes,
 	},
+	proto::ClientState as RawClientState,
 };
 use beefy_prover::helpers::{fetch_timestamp_extrinsic_with_proof, TimeStampExtWithProof};
-use codec::{Decode, Encode};
+use codec::{Compact, Decode, Encode};
 use finality_grandpa_rpc::GrandpaApiClient;
 use futures::stream::StreamExt;
 use grandpa_client_primitives::{
 	justification::GrandpaJustification, parachain_header_storage_key, FinalityProof,
-	ParachainHeaderProofs, ParachainHeadersWithFinalityProof,
+	HostFunctions, ParachainHeaderProofs, ParachainHeadersWithFinalityProof,
 };
 use grandpa_prover::{GrandpaProver, JustificationNotification};
 use hyperspace_core::substrate::DefaultConfig as PolkadotConfig;
 use ibc::{
 	core::{
 		ics02_client::{
+			client_def::{ClientDef, ConsensusUpdateResult},
 			client_state::ClientState as _,
 			context::{ClientKeeper, ClientReader},
 			handler::{dispatch, ClientResult::Update},
@@ -41,7 +44,12 @@ use ibc::{
 				create_client::MsgCreateAnyClient, update_client::MsgUpdateAnyClient, ClientMsg,
 			},
 		},
-		ics24_host::identifier::{ChainId, ClientId},
+		ics03_connection::connection::ConnectionEnd,
+		ics23_commitment::commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
+		ics24_host::{
+			identifier::{ChainId, ClientId, ConnectionId},
+			path::{ConnectionsPath, Path},
+		},
 	},
 	events::IbcEvent,
 	handler::HandlerOutput,
@@ -49,10 +57,28 @@ use ibc::{
 	test_utils::get_dummy_account_id,
 	Height,
 };
-use light_client_common::config::RuntimeStorage;
-use sp_core::{hexdisplay::AsBytesRef, H256};
-use std::time::Duration;
+use light_client_common::{config::RuntimeStorage, RelayChain};
+use sp_consensus_grandpa::{
+	AuthorityId, AuthorityList, ConsensusLog, ScheduledChange, GRANDPA_ENGINE_ID,
+};
+use sp_core::{
+	ed25519,
+	hexdisplay::AsBytesRef,
+	storage::{ChildInfo, StateVersion, Storage, StorageChild},
+	Pair, H256,
+};
+use sp_runtime::{
+	generic::{Digest, DigestItem},
+	traits::Header as _,
+};
+use sp_state_machine::{
+	prove_child_read, prove_read_on_trie_backend, InMemoryBackend, TrieBackendBuilder,
+};
+use sp_trie::{generate_trie_proof, LayoutV0, MemoryDB, TrieDBMutBuilder, TrieMut};
+use std::{collections::BTreeMap, time::Duration};
 use subxt::config::substrate::{BlakeTwo256, SubstrateHeader};
+use tendermint::time::Time;
+use tendermint_proto::Protobuf;
 
 #[tokio::test]
 async fn test_continuous_update_of_grandpa_client() {
@@ -146,6 +172,9 @@ async fn test_continuous_update_of_grandpa_client() {
 			para_id: prover.para_id,
 			current_set_id: client_state.current_set_id,
 			current_authorities: client_state.current_authorities,
+			commitment_prefix: Default::default(),
+			custom_trusting_period: None,
+			max_consensus_states: None,
 			_phantom: Default::default(),
 		};
 		let subxt_block_number: subxt::rpc::types::BlockNumber = decoded_para_head.number.into();
@@ -306,3 +335,1046 @@ async fn test_continuous_update_of_grandpa_client() {
 		}
 	}
 }
+
+fn client_state_at(latest_para_height: u32) -> ClientState<HostFunctionsManager> {
+	ClientState { para_id: 2000, latest_para_height, ..Default::default() }
+}
+
+#[test]
+fn test_verify_height() {
+	let client_state = client_state_at(100);
+
+	// at the stored height
+	client_state.verify_height(Height::new(2000, 100)).unwrap();
+	// below the stored height
+	client_state.verify_height(Height::new(2000, 99)).unwrap();
+	// above the stored height
+	assert!(client_state.verify_height(Height::new(2000, 101)).is_err());
+}
+
+#[test]
+fn test_verify_height_frozen() {
+	let client_state =
+		ClientState { frozen_height: Some(Height::new(2000, 90)), ..client_state_at(100) };
+
+	// below the frozen height
+	client_state.verify_height(Height::new(2000, 89)).unwrap();
+	// at or above the frozen height
+	for height in [90, 100] {
+		let err = client_state.verify_height(Height::new(2000, height)).unwrap_err();
+		assert!(err.to_string().contains("Client has been frozen"), "{err}");
+	}
+}
+
+#[test]
+fn test_client_state_codec_roundtrip() {
+	let client_state = ClientState {
+		relay_chain: RelayChain::Kusama,
+		latest_relay_height: 50,
+		latest_relay_hash: H256::repeat_byte(9),
+		frozen_height: Some(Height::new(2000, 90)),
+		current_set_id: 3,
+		current_authorities: authorities(0),
+		..client_state_at(100)
+	};
+
+	let encoded = client_state.encode_vec().unwrap();
+	let decoded = ClientState::<HostFunctionsManager>::decode_vec(&encoded).unwrap();
+
+	assert_eq!(decoded, client_state);
+}
+
+#[test]
+fn test_client_state_custom_trusting_period() {
+	let trusting_period = Duration::from_secs(60 * 60);
+	let client_state =
+		ClientState { custom_trusting_period: Some(trusting_period), ..client_state_at(100) };
+	assert_eq!(client_state.trusting_period(), trusting_period);
+	assert_eq!(client_state.unbonding_period(), client_state.relay_chain.unbonding_period());
+	assert!(!client_state.expired(trusting_period));
+	assert!(client_state.expired(trusting_period + Duration::from_secs(1)));
+
+	let encoded = client_state.encode_vec().unwrap();
+	let decoded = ClientState::<HostFunctionsManager>::decode_vec(&encoded).unwrap();
+	assert_eq!(decoded, client_state);
+
+	// a trusting period that outlasts the unbonding period is rejected when decoding
+	let mut raw = RawClientState::from(client_state);
+	raw.custom_trusting_period = Some(RelayChain::Rococo.unbonding_period().as_secs());
+	let err = ClientState::<HostFunctionsManager>::try_from(raw).unwrap_err();
+	assert!(err.to_string().contains("must be non-zero and shorter"), "{err}");
+}
+
+#[test]
+fn test_client_state_max_consensus_states() {
+	let client_state = ClientState { max_consensus_states: Some(3), ..client_state_at(100) };
+	let encoded = client_state.encode_vec().unwrap();
+	let decoded = ClientState::<HostFunctionsManager>::decode_vec(&encoded).unwrap();
+	assert_eq!(decoded, client_state);
+
+	let mut raw = RawClientState::from(client_state);
+	raw.max_consensus_states = Some(0);
+	let err = ClientState::<HostFunctionsManager>::try_from(raw).unwrap_err();
+	assert!(err.to_string().contains("must be greater than zero"), "{err}");
+}
+
+#[test]
+fn test_consensus_state_codec_roundtrip() {
+	let consensus_state =
+		ConsensusState::new(vec![1; 32], Time::from_unix_timestamp(1_000, 5).unwrap());
+
+	let encoded = consensus_state.encode_vec().unwrap();
+	let decoded = ConsensusState::decode_vec(&encoded).unwrap();
+
+	assert_eq!(decoded, consensus_state);
+}
+
+#[test]
+fn test_header_codec_roundtrip() {
+	let relay_header = RelayChainHeader::new(
+		42,
+		Default::default(),
+		H256::repeat_byte(1),
+		H256::repeat_byte(2),
+		Default::default(),
+	);
+	let parachain_headers = BTreeMap::from([(
+		relay_header.hash(),
+		ParachainHeaderProofs {
+			state_proof: vec![vec![3; 8], vec![4; 8]],
+			extrinsic: vec![5; 16],
+			extrinsic_proof: vec![vec![6; 8]],
+		},
+	)]);
+	let header = Header {
+		finality_proof: FinalityProof {
+			block: relay_header.hash(),
+			justification: vec![7; 64],
+			unknown_headers: vec![relay_header.clone()],
+		},
+		parachain_headers,
+		height: Height::new(2000, 100),
+	};
+
+	let encoded = header.clone().encode_vec().unwrap();
+	let decoded = Header::decode_vec(&encoded).unwrap();
+
+	assert_eq!(decoded.height, header.height);
+	assert_eq!(decoded.finality_proof, header.finality_proof);
+	assert_eq!(decoded.parachain_headers.keys().collect::<Vec<_>>(), vec![&relay_header.hash()]);
+	assert_eq!(decoded.encode_vec().unwrap(), encoded);
+}
+
+fn mock_context() -> MockContext<MockClientTypes> {
+	MockContext::<MockClientTypes>::new(
+		ChainId::new("mockgaiaA".to_string(), 1),
+		MockHostType::Mock,
+		5,
+		Height::new(1, 11),
+	)
+}
+
+#[test]
+fn test_verify_client_message_rejects_stale_relay_chain_header() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client_state = ClientState { latest_relay_height: 50, ..client_state_at(100) };
+
+	for number in [40, 50] {
+		let relay_header = RelayChainHeader::new(
+			number,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let header = Header {
+			finality_proof: FinalityProof {
+				block: relay_header.hash(),
+				justification: vec![],
+				unknown_headers: vec![relay_header],
+			},
+			parachain_headers: Default::default(),
+			height: Height::new(2000, 101),
+		};
+
+		let err = GrandpaClient::<HostFunctionsManager>::default()
+			.verify_client_message(
+				&ctx,
+				client_id.clone(),
+				client_state.clone(),
+				ClientMessage::Header(header),
+			)
+			.unwrap_err();
+		assert!(err.to_string().contains("Header doesn't advance the client"), "{err}");
+	}
+}
+
+const TIMESTAMP_MILLIS: u64 = 1_700_000_000_000;
+
+fn authority_pairs(seed: u8) -> Vec<ed25519::Pair> {
+	(0..3).map(|i| ed25519::Pair::from_seed(&[seed + i; 32])).collect()
+}
+
+fn authorities(seed: u8) -> AuthorityList {
+	authority_pairs(seed)
+		.iter()
+		.map(|pair| (AuthorityId::from(pair.public()), 1))
+		.collect()
+}
+
+/// Signs the header's finality target with the authorities derived from `seed`.
+fn sign_update(header: &mut Header, set_id: u64, seed: u8) {
+	const ROUND: u64 = 1;
+	let target = header.finality_proof.unknown_headers.last().unwrap();
+	let precommit =
+		finality_grandpa::Precommit { target_hash: target.hash(), target_number: target.number };
+	let message = finality_grandpa::Message::Precommit(precommit.clone());
+	let precommits = authority_pairs(seed)
+		.iter()
+		.map(|pair| finality_grandpa::SignedPrecommit {
+			precommit: precommit.clone(),
+			signature: pair.sign(&(message.clone(), ROUND, set_id).encode()).into(),
+			id: pair.public().into(),
+		})
+		.collect();
+	let justification = GrandpaJustification::<RelayChainHeader> {
+		round: ROUND,
+		commit: finality_grandpa::Commit {
+			target_hash: target.hash(),
+			target_number: target.number,
+			precommits,
+		},
+		votes_ancestries: vec![],
+	};
+	header.finality_proof.justification = justification.encode();
+}
+
+/// Builds a state proof of a parachain header with the given number, along with a proof of its
+/// timestamp extrinsic, returning the relay chain state root.
+fn parachain_header_proofs(para_id: u32, number: u32) -> (H256, ParachainHeaderProofs) {
+	let mut db = MemoryDB::<sp_runtime::traits::BlakeTwo256>::default();
+
+	// the timestamp extrinsic is the first extrinsic of the parachain block
+	// a bare v4 extrinsic calling `Timestamp::set`, prefixed with its length
+	let timestamp_extrinsic = (4u8, 1u8, 0u8, Compact(TIMESTAMP_MILLIS)).encode().encode();
+	let key = Compact(0u32).encode();
+	let extrinsics_root = {
+		let mut root = Default::default();
+		let mut trie =
+			<TrieDBMutBuilder<LayoutV0<sp_runtime::traits::BlakeTwo256>>>::new(&mut db, &mut root)
+				.build();
+		trie.insert(&key, &timestamp_extrinsic).unwrap();
+		*trie.root()
+	};
+	let extrinsic_proof =
+		generate_trie_proof::<LayoutV0<sp_runtime::traits::BlakeTwo256>, _, _, _>(
+			&db,
+			extrinsics_root,
+			vec![&key],
+		)
+		.unwrap();
+
+	let parachain_header = RelayChainHeader::new(
+		number,
+		extrinsics_root,
+		Default::default(),
+		Default::default(),
+		Default::default(),
+	);
+	let key = parachain_header_storage_key(para_id);
+	let mut state_root = Default::default();
+	{
+		let mut trie = TrieDBMutBuilder::<LayoutV0<sp_runtime::traits::BlakeTwo256>>::new(
+			&mut db,
+			&mut state_root,
+		)
+		.build();
+		trie.insert(key.as_ref(), &parachain_header.encode().encode()).unwrap();
+	}
+	let state_proof = prove_read_on_trie_backend(
+		&TrieBackendBuilder::new(db, state_root).build(),
+		&[key.as_ref()],
+	)
+	.unwrap()
+	.into_nodes()
+	.into_iter()
+	.collect::<Vec<_>>();
+
+	(
+		state_root,
+		ParachainHeaderProofs { state_proof, extrinsic: timestamp_extrinsic, extrinsic_proof },
+	)
+}
+
+/// Builds a header finalizing three new relay chain blocks, the last one carrying `digest` and a
+/// state proof for a new parachain header.
+fn relay_chain_update(client_state: &ClientState<HostFunctionsManager>, digest: Digest) -> Header {
+	relay_chain_update_with_parachain_headers(client_state, digest, 1)
+}
+
+/// Builds a header finalizing three new relay chain blocks, the last one carrying `digest`. Each of
+/// the last `parachain_headers` relay chain blocks includes a new parachain header.
+fn relay_chain_update_with_parachain_headers(
+	client_state: &ClientState<HostFunctionsManager>,
+	digest: Digest,
+	parachain_headers: u32,
+) -> Header {
+	const RELAY_CHAIN_BLOCKS: u32 = 3;
+	let mut headers = vec![];
+	let mut proofs = BTreeMap::new();
+	let mut parent_hash = client_state.latest_relay_hash;
+	let mut para_height = client_state.latest_para_height;
+	for i in 1..=RELAY_CHAIN_BLOCKS {
+		let digest = if i == RELAY_CHAIN_BLOCKS { digest.clone() } else { Default::default() };
+		let parachain_header = if i + parachain_headers > RELAY_CHAIN_BLOCKS {
+			para_height += 1;
+			Some(parachain_header_proofs(client_state.para_id, para_height))
+		} else {
+			None
+		};
+		let state_root = parachain_header.as_ref().map(|(root, _)| *root).unwrap_or_default();
+		let header = RelayChainHeader::new(
+			client_state.latest_relay_height + i,
+			Default::default(),
+			state_root,
+			parent_hash,
+			digest,
+		);
+		parent_hash = header.hash();
+		if let Some((_, proof)) = parachain_header {
+			proofs.insert(header.hash(), proof);
+		}
+		headers.push(header);
+	}
+
+	Header {
+		finality_proof: FinalityProof {
+			block: parent_hash,
+			justification: vec![],
+			unknown_headers: headers,
+		},
+		parachain_headers: proofs,
+		height: Height::new(client_state.para_id as u64, para_height as u64),
+	}
+}
+
+fn update_client_state() -> ClientState<HostFunctionsManager> {
+	ClientState {
+		latest_relay_height: 50,
+		latest_relay_hash: H256::repeat_byte(9),
+		current_set_id: 1,
+		current_authorities: authorities(0),
+		..client_state_at(100)
+	}
+}
+
+#[test]
+fn test_update_state() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client_state = update_client_state();
+	let header = relay_chain_update(&client_state, Default::default());
+	let target = header.finality_proof.block;
+
+	let (new_client_state, consensus_update) = GrandpaClient::<HostFunctionsManager>::default()
+		.update_state(&ctx, client_id, client_state, ClientMessage::Header(header))
+		.unwrap();
+
+	assert_eq!(new_client_state.latest_relay_height, 53);
+	assert_eq!(new_client_state.latest_relay_hash, target);
+	assert_eq!(new_client_state.latest_para_height, 101);
+	assert_eq!(new_client_state.current_set_id, 1);
+	assert_eq!(new_client_state.current_authorities, authorities(0));
+
+	let consensus_states = match consensus_update {
+		ConsensusUpdateResult::Batch(consensus_states) => consensus_states,
+		ConsensusUpdateResult::Single(_) => panic!("expected a batch of consensus states"),
+	};
+	let expected = ConsensusState::new(
+		vec![0; 32],
+		Time::from_unix_timestamp((TIMESTAMP_MILLIS / 1000) as i64, 0).unwrap(),
+	);
+	assert_eq!(
+		consensus_states,
+		vec![(Height::new(2000, 101), AnyConsensusState::Grandpa(expected))]
+	);
+}
+
+#[test]
+fn test_update_state_with_multiple_parachain_headers() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client_state = update_client_state();
+	let header = relay_chain_update_with_parachain_headers(&client_state, Default::default(), 3);
+
+	let (new_client_state, consensus_update) = GrandpaClient::<HostFunctionsManager>::default()
+		.update_state(&ctx, client_id, client_state, ClientMessage::Header(header))
+		.unwrap();
+
+	assert_eq!(new_client_state.latest_relay_height, 53);
+	assert_eq!(new_client_state.latest_para_height, 103);
+	let mut heights = match consensus_update {
+		ConsensusUpdateResult::Batch(consensus_states) =>
+			consensus_states.into_iter().map(|(height, _)| height).collect::<Vec<_>>(),
+		ConsensusUpdateResult::Single(_) => panic!("expected a batch of consensus states"),
+	};
+	// consensus states are ordered by relay chain block hash
+	heights.sort();
+	assert_eq!(
+		heights,
+		vec![Height::new(2000, 101), Height::new(2000, 102), Height::new(2000, 103)]
+	);
+}
+
+#[test]
+fn test_update_state_rotates_authorities() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client_state = update_client_state();
+	let change = ScheduledChange { next_authorities: authorities(10), delay: 0 };
+	let mut digest = Digest::default();
+	digest.push(DigestItem::Consensus(
+		GRANDPA_ENGINE_ID,
+		ConsensusLog::ScheduledChange(change).encode(),
+	));
+	let header = relay_chain_update(&client_state, digest);
+
+	let (new_client_state, _) = GrandpaClient::<HostFunctionsManager>::default()
+		.update_state(&ctx, client_id, client_state, ClientMessage::Header(header))
+		.unwrap();
+
+	assert_eq!(new_client_state.latest_relay_height, 53);
+	assert_eq!(new_client_state.current_set_id, 2);
+	assert_eq!(new_client_state.current_authorities, authorities(10));
+}
+
+#[test]
+fn test_conflicting_header_freezes_client() {
+	let mut ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client_state = update_client_state();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let height = Height::new(2000, 101);
+
+	// the honest update is processed and its consensus state stored.
+	let header = relay_chain_update(&client_state, Default::default());
+	let (_, consensus_update) = client
+		.update_state(&ctx, client_id.clone(), client_state.clone(), ClientMessage::Header(header))
+		.unwrap();
+	let consensus_state = match consensus_update {
+		ConsensusUpdateResult::Batch(mut consensus_states) => consensus_states.remove(0).1,
+		ConsensusUpdateResult::Single(_) => panic!("expected a batch of consensus states"),
+	};
+	ctx.store_consensus_state(client_id.clone(), height, consensus_state).unwrap();
+
+	// replaying the same update is not misbehaviour.
+	let header = relay_chain_update(&client_state, Default::default());
+	assert!(!client
+		.check_for_misbehaviour(
+			&ctx,
+			client_id.clone(),
+			client_state.clone(),
+			ClientMessage::Header(header)
+		)
+		.unwrap());
+
+	// a second, finalized parachain header at the same height conflicts with the stored one.
+	let conflicting = ConsensusState::new(
+		vec![1; 32],
+		Time::from_unix_timestamp((TIMESTAMP_MILLIS / 1000) as i64, 0).unwrap(),
+	);
+	ctx.store_consensus_state(client_id.clone(), height, AnyConsensusState::Grandpa(conflicting))
+		.unwrap();
+	let header = relay_chain_update(&client_state, Default::default());
+	assert!(client
+		.check_for_misbehaviour(
+			&ctx,
+			client_id.clone(),
+			client_state.clone(),
+			ClientMessage::Header(header.clone())
+		)
+		.unwrap());
+
+	let frozen = client
+		.update_state_on_misbehaviour(client_state, ClientMessage::Header(header))
+		.unwrap();
+	assert_eq!(frozen.frozen_height, Some(Height::new(2000, 100)));
+}
+
+#[test]
+fn test_forced_change_is_misbehaviour() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client_state = update_client_state();
+	let change = ScheduledChange { next_authorities: authorities(10), delay: 0 };
+	let mut digest = Digest::default();
+	digest.push(DigestItem::Consensus(
+		GRANDPA_ENGINE_ID,
+		ConsensusLog::ForcedChange(0, change).encode(),
+	));
+	let header = relay_chain_update(&client_state, digest);
+
+	assert!(GrandpaClient::<HostFunctionsManager>::default()
+		.check_for_misbehaviour(&ctx, client_id, client_state, ClientMessage::Header(header))
+		.unwrap());
+}
+
+#[test]
+fn test_verify_client_message_accepts_signed_update() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client_state = update_client_state();
+	let mut header = relay_chain_update(&client_state, Default::default());
+	sign_update(&mut header, client_state.current_set_id, 0);
+
+	GrandpaClient::<HostFunctionsManager>::default()
+		.verify_client_message(&ctx, client_id, client_state, ClientMessage::Header(header))
+		.unwrap();
+}
+
+#[test]
+fn test_verify_client_message_rejects_invalid_update() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let client_state = update_client_state();
+	let verify = |client_state: ClientState<HostFunctionsManager>, header: Header| {
+		client
+			.verify_client_message(
+				&ctx,
+				client_id.clone(),
+				client_state,
+				ClientMessage::Header(header),
+			)
+			.unwrap_err()
+			.to_string()
+	};
+
+	// signed by an unknown authority set
+	let mut header = relay_chain_update(&client_state, Default::default());
+	sign_update(&mut header, client_state.current_set_id, 10);
+	let err = verify(client_state.clone(), header);
+	assert!(err.contains("InvalidCommit"), "{err}");
+
+	// signed for another set id
+	let mut header = relay_chain_update(&client_state, Default::default());
+	sign_update(&mut header, client_state.current_set_id + 1, 0);
+	let err = verify(client_state.clone(), header);
+	assert!(err.contains("BadSignature"), "{err}");
+
+	// doesn't extend the latest relay chain block known to the client
+	let mut header = relay_chain_update(&client_state, Default::default());
+	sign_update(&mut header, client_state.current_set_id, 0);
+	let err = verify(
+		ClientState { latest_relay_hash: H256::repeat_byte(8), ..client_state.clone() },
+		header,
+	);
+	assert!(err.contains("don't form a contiguous chain"), "{err}");
+}
+
+#[test]
+fn test_duplicate_update_is_rejected() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let client_state = update_client_state();
+	let mut header = relay_chain_update(&client_state, Default::default());
+	sign_update(&mut header, client_state.current_set_id, 0);
+
+	client
+		.verify_client_message(
+			&ctx,
+			client_id.clone(),
+			client_state.clone(),
+			ClientMessage::Header(header.clone()),
+		)
+		.unwrap();
+	let (client_state, _) = client
+		.update_state(&ctx, client_id.clone(), client_state, ClientMessage::Header(header.clone()))
+		.unwrap();
+	HostFunctionsManager::insert_relay_header_hashes(&[client_state.latest_relay_hash]);
+
+	// the same update doesn't advance the client anymore, even though its blocks are finalized.
+	let err = client
+		.verify_client_message(
+			&ctx,
+			client_id.clone(),
+			client_state.clone(),
+			ClientMessage::Header(header.clone()),
+		)
+		.unwrap_err();
+	assert!(err.to_string().contains("Header doesn't advance the client"), "{err}");
+	let err = client
+		.update_state(&ctx, client_id, client_state, ClientMessage::Header(header))
+		.unwrap_err();
+	assert!(err.to_string().contains("can only be updated to new relay chain height"), "{err}");
+}
+
+/// Rococo relay chain block #11342, captured on 2021-04-08.
+const ROCOCO_11342: &[u8] = include_bytes!("fixtures/header-rococo-11342");
+
+/// A client tracking parachain 2000 on Rococo, whose latest relay chain block is the parent of the
+/// captured one.
+fn rococo_client_state(captured: &RelayChainHeader) -> ClientState<HostFunctionsManager> {
+	ClientState {
+		relay_chain: RelayChain::Rococo,
+		latest_relay_height: captured.number - 1,
+		latest_relay_hash: captured.parent_hash,
+		current_set_id: 1,
+		current_authorities: authorities(0),
+		..client_state_at(100)
+	}
+}
+
+/// Builds an update finalizing the captured Rococo block and a child of it which includes the next
+/// parachain header. The captured block's own justification isn't available, so the update is
+/// signed by the authorities derived from `seed`.
+fn rococo_update(captured: &RelayChainHeader, seed: u8) -> Header {
+	let (state_root, proof) = parachain_header_proofs(2000, 101);
+	let child = RelayChainHeader::new(
+		captured.number + 1,
+		Default::default(),
+		state_root,
+		captured.hash(),
+		Default::default(),
+	);
+	let mut header = Header {
+		finality_proof: FinalityProof {
+			block: child.hash(),
+			justification: vec![],
+			unknown_headers: vec![captured.clone(), child.clone()],
+		},
+		parachain_headers: BTreeMap::from([(child.hash(), proof)]),
+		height: Height::new(2000, 101),
+	};
+	sign_update(&mut header, 1, seed);
+	header
+}
+
+#[test]
+fn test_update_with_captured_rococo_header() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let captured = RelayChainHeader::decode(&mut &ROCOCO_11342[..]).unwrap();
+	assert_eq!(captured.number, 11342);
+	assert_eq!(captured.encode(), ROCOCO_11342);
+	let client_state = rococo_client_state(&captured);
+	let header = rococo_update(&captured, 0);
+	let target = header.finality_proof.block;
+
+	client
+		.verify_client_message(
+			&ctx,
+			client_id.clone(),
+			client_state.clone(),
+			ClientMessage::Header(header.clone()),
+		)
+		.unwrap();
+	let (new_client_state, consensus_update) = client
+		.update_state(&ctx, client_id, client_state, ClientMessage::Header(header))
+		.unwrap();
+
+	assert_eq!(new_client_state.latest_relay_height, 11343);
+	assert_eq!(new_client_state.latest_relay_hash, target);
+	assert_eq!(new_client_state.latest_para_height, 101);
+	assert_eq!(new_client_state.current_set_id, 1);
+	let heights = match consensus_update {
+		ConsensusUpdateResult::Batch(consensus_states) =>
+			consensus_states.into_iter().map(|(height, _)| height).collect::<Vec<_>>(),
+		ConsensusUpdateResult::Single(_) => panic!("expected a batch of consensus states"),
+	};
+	assert_eq!(heights, vec![Height::new(2000, 101)]);
+}
+
+#[test]
+fn test_update_with_captured_rococo_header_rejects_invalid_update() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let captured = RelayChainHeader::decode(&mut &ROCOCO_11342[..]).unwrap();
+	let client_state = rococo_client_state(&captured);
+	let verify = |header: Header| {
+		client
+			.verify_client_message(
+				&ctx,
+				client_id.clone(),
+				client_state.clone(),
+				ClientMessage::Header(header),
+			)
+			.unwrap_err()
+			.to_string()
+	};
+
+	// signed by an unknown authority set
+	let err = verify(rococo_update(&captured, 10));
+	assert!(err.contains("InvalidCommit"), "{err}");
+
+	// the captured block's seal is stripped, so the finalized child no longer descends from it
+	let mut header = rococo_update(&captured, 0);
+	header.finality_proof.unknown_headers[0].digest.logs.pop();
+	let err = verify(header);
+	assert!(err.contains("don't form a contiguous chain"), "{err}");
+}
+
+/// Stores the upgraded client state at `client_path` and the upgraded consensus state at the
+/// consensus state upgrade path, returning the state root and proofs of reading both upgrade paths.
+fn upgrade_proofs(
+	client_path: &[u8],
+	client_state: &ClientState<HostFunctionsManager>,
+	consensus_state: &ConsensusState,
+) -> (H256, Vec<u8>, Vec<u8>) {
+	let mut db = MemoryDB::<sp_runtime::traits::BlakeTwo256>::default();
+	let mut state_root = Default::default();
+	{
+		let mut trie = TrieDBMutBuilder::<LayoutV0<sp_runtime::traits::BlakeTwo256>>::new(
+			&mut db,
+			&mut state_root,
+		)
+		.build();
+		let client_state = AnyClientState::Grandpa(client_state.clone()).encode_vec().unwrap();
+		trie.insert(client_path, &client_state.encode()).unwrap();
+		let consensus_state =
+			AnyConsensusState::Grandpa(consensus_state.clone()).encode_vec().unwrap();
+		trie.insert(CONSENSUS_STATE_UPGRADE_PATH, &consensus_state.encode()).unwrap();
+	}
+	let backend = TrieBackendBuilder::new(db, state_root).build();
+	let prove = |key: &[u8]| {
+		prove_read_on_trie_backend(&backend, &[key])
+			.unwrap()
+			.into_nodes()
+			.into_iter()
+			.collect::<Vec<_>>()
+			.encode()
+	};
+	(state_root, prove(CLIENT_STATE_UPGRADE_PATH), prove(CONSENSUS_STATE_UPGRADE_PATH))
+}
+
+#[test]
+fn test_verify_upgrade_and_update_state() {
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let client_state = client_state_at(100);
+	let upgraded_client_state = ClientState {
+		current_set_id: 5,
+		current_authorities: authorities(10),
+		..client_state_at(200)
+	};
+	let timestamp = Time::from_unix_timestamp((TIMESTAMP_MILLIS / 1000) as i64, 0).unwrap();
+	let upgraded_consensus_state = ConsensusState::new(vec![7; 32], timestamp);
+	// proves `proven_client_state` at `client_path` and verifies the upgrade to
+	// `upgraded_client_state` against it.
+	let upgrade = |client_path: &[u8], proven_client_state: &ClientState<HostFunctionsManager>| {
+		let (state_root, client_proof, consensus_proof) =
+			upgrade_proofs(client_path, proven_client_state, &upgraded_consensus_state);
+		let mut ctx = mock_context();
+		ctx.store_consensus_state(
+			client_id.clone(),
+			Height::new(2000, 100),
+			AnyConsensusState::Grandpa(ConsensusState::new(
+				state_root.as_bytes().to_vec(),
+				timestamp,
+			)),
+		)
+		.unwrap();
+		client.verify_upgrade_and_update_state(
+			&ctx,
+			client_id.clone(),
+			&client_state,
+			&upgraded_client_state,
+			&upgraded_consensus_state,
+			client_proof,
+			consensus_proof,
+		)
+	};
+
+	let (new_client_state, consensus_update) =
+		upgrade(CLIENT_STATE_UPGRADE_PATH, &upgraded_client_state).unwrap();
+	assert_eq!(new_client_state, upgraded_client_state);
+	match consensus_update {
+		ConsensusUpdateResult::Single(consensus_state) => assert_eq!(
+			consensus_state,
+			AnyConsensusState::Grandpa(upgraded_consensus_state.clone())
+		),
+		ConsensusUpdateResult::Batch(_) => panic!("expected a single consensus state"),
+	}
+
+	// the proof commits to another upgraded client state
+	let err = upgrade(
+		CLIENT_STATE_UPGRADE_PATH,
+		&ClientState { current_set_id: 6, ..upgraded_client_state.clone() },
+	)
+	.unwrap_err();
+	let err = err.to_string();
+	assert!(err.contains("Invalid proof for client state upgrade"), "{err}");
+
+	// the counterparty stored the upgraded client state under a different path
+	let err = upgrade(b"other-client-state-upgrade-path", &upgraded_client_state).unwrap_err();
+	let err = err.to_string();
+	assert!(err.contains("No upgraded client state found"), "{err}");
+}
+
+#[test]
+fn test_verify_client_message_rejects_other_para_id() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let client_state = update_client_state();
+	let other_para = ClientState { para_id: 2001, ..client_state.clone() };
+
+	// the header claims to be for another parachain
+	let mut header = relay_chain_update(&other_para, Default::default());
+	sign_update(&mut header, client_state.current_set_id, 0);
+	let err = client
+		.verify_client_message(
+			&ctx,
+			client_id.clone(),
+			client_state.clone(),
+			ClientMessage::Header(header.clone()),
+		)
+		.unwrap_err();
+	assert!(err.to_string().contains("Para id mismatch"), "{err}");
+
+	// the header claims to be for our parachain, but the relay chain state only includes a header
+	// of another one.
+	header.height = Height::new(client_state.para_id as u64, header.height.revision_height);
+	let err = client
+		.verify_client_message(&ctx, client_id, client_state, ClientMessage::Header(header))
+		.unwrap_err();
+	assert!(err.to_string().contains("parachain header not found"), "{err}");
+}
+
+/// Builds two finality proofs for forks of the relay chain starting at the client's latest relay
+/// chain block, signed by the authorities derived from the given seeds.
+fn conflicting_finality_proofs(
+	client_state: &ClientState<HostFunctionsManager>,
+	first_seed: u8,
+	second_seed: u8,
+) -> Misbehaviour {
+	let mut first = relay_chain_update_with_parachain_headers(client_state, Default::default(), 1);
+	sign_update(&mut first, client_state.current_set_id, first_seed);
+	let mut second = relay_chain_update_with_parachain_headers(client_state, Default::default(), 2);
+	sign_update(&mut second, client_state.current_set_id, second_seed);
+	Misbehaviour {
+		first_finality_proof: first.finality_proof,
+		second_finality_proof: second.finality_proof,
+	}
+}
+
+#[test]
+fn test_conflicting_justifications_freeze_client() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let client_state = update_client_state();
+	HostFunctionsManager::insert_relay_header_hashes(&[client_state.latest_relay_hash]);
+	let misbehaviour = conflicting_finality_proofs(&client_state, 0, 0);
+	assert_ne!(misbehaviour.first_finality_proof.block, misbehaviour.second_finality_proof.block);
+
+	let client_message = ClientMessage::Misbehaviour(misbehaviour);
+	client
+		.verify_client_message(
+			&ctx,
+			client_id.clone(),
+			client_state.clone(),
+			client_message.clone(),
+		)
+		.unwrap();
+	assert!(client
+		.check_for_misbehaviour(&ctx, client_id, client_state.clone(), client_message.clone())
+		.unwrap());
+
+	let frozen = client.update_state_on_misbehaviour(client_state, client_message).unwrap();
+	assert_eq!(frozen.frozen_height, Some(Height::new(2000, 100)));
+}
+
+#[test]
+fn test_finality_proofs_of_the_same_fork_are_not_misbehaviour() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let client_state = update_client_state();
+	HostFunctionsManager::insert_relay_header_hashes(&[client_state.latest_relay_hash]);
+
+	// the second proof finalizes the parent of the first one's block
+	let mut first = relay_chain_update(&client_state, Default::default());
+	sign_update(&mut first, client_state.current_set_id, 0);
+	let mut second = first.clone();
+	second.finality_proof.unknown_headers.pop();
+	second.finality_proof.block = second.finality_proof.unknown_headers.last().unwrap().hash();
+	sign_update(&mut second, client_state.current_set_id, 0);
+
+	let client_message = ClientMessage::Misbehaviour(Misbehaviour {
+		first_finality_proof: first.finality_proof,
+		second_finality_proof: second.finality_proof,
+	});
+	client
+		.verify_client_message(
+			&ctx,
+			client_id.clone(),
+			client_state.clone(),
+			client_message.clone(),
+		)
+		.unwrap();
+	assert!(!client
+		.check_for_misbehaviour(&ctx, client_id, client_state, client_message)
+		.unwrap());
+}
+
+#[test]
+fn test_verify_misbehaviour_rejects_invalid_proofs() {
+	let ctx = mock_context();
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let client_state = update_client_state();
+	HostFunctionsManager::insert_relay_header_hashes(&[client_state.latest_relay_hash]);
+	let verify = |misbehaviour: Misbehaviour| {
+		client
+			.verify_client_message(
+				&ctx,
+				client_id.clone(),
+				client_state.clone(),
+				ClientMessage::Misbehaviour(misbehaviour),
+			)
+			.unwrap_err()
+			.to_string()
+	};
+
+	// the second fork isn't finalized by the current authority set
+	let err = verify(conflicting_finality_proofs(&client_state, 0, 10));
+	assert!(err.contains("Invalid justification"), "{err}");
+
+	// both proofs finalize the same block
+	let misbehaviour = conflicting_finality_proofs(&client_state, 0, 0);
+	let err = verify(Misbehaviour {
+		second_finality_proof: misbehaviour.first_finality_proof.clone(),
+		..misbehaviour
+	});
+	assert!(err.contains("Misbehaviour proofs are for the same block"), "{err}");
+}
+
+#[test]
+fn test_verify_upgrade_rejects_invalid_upgraded_client_state() {
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let client_state = client_state_at(100);
+	let timestamp = Time::from_unix_timestamp((TIMESTAMP_MILLIS / 1000) as i64, 0).unwrap();
+	let upgraded_consensus_state = ConsensusState::new(vec![7; 32], timestamp);
+	let upgrade = |upgraded_client_state: ClientState<HostFunctionsManager>| {
+		let (state_root, client_proof, consensus_proof) = upgrade_proofs(
+			CLIENT_STATE_UPGRADE_PATH,
+			&upgraded_client_state,
+			&upgraded_consensus_state,
+		);
+		let mut ctx = mock_context();
+		ctx.store_consensus_state(
+			client_id.clone(),
+			Height::new(2000, 100),
+			AnyConsensusState::Grandpa(ConsensusState::new(
+				state_root.as_bytes().to_vec(),
+				timestamp,
+			)),
+		)
+		.unwrap();
+		client
+			.verify_upgrade_and_update_state(
+				&ctx,
+				client_id.clone(),
+				&client_state,
+				&upgraded_client_state,
+				&upgraded_consensus_state,
+				client_proof,
+				consensus_proof,
+			)
+			.unwrap_err()
+			.to_string()
+	};
+
+	// the upgrade height isn't above the current height
+	for height in [90, 100] {
+		let err = upgrade(client_state_at(height));
+		assert!(err.contains("must be greater than the current client height"), "{err}");
+	}
+
+	// the chain committed to a value for the client-chosen frozen height
+	let err =
+		upgrade(ClientState { frozen_height: Some(Height::new(2000, 1)), ..client_state_at(200) });
+	assert!(err.contains("Upgraded client state must not be frozen"), "{err}");
+}
+
+/// Stores `value` at `path` in the child trie of the given commitment prefix, returning the state
+/// root and a proof of reading it.
+fn child_trie_proof(
+	prefix: &[u8],
+	path: Path,
+	value: Vec<u8>,
+) -> (CommitmentRoot, CommitmentProofBytes) {
+	let child_info = ChildInfo::new_default(prefix);
+	let mut key = prefix.to_vec();
+	key.extend(path.to_string().as_bytes());
+	let storage = Storage {
+		top: Default::default(),
+		children_default: [(
+			child_info.storage_key().to_vec(),
+			StorageChild {
+				data: [(key.clone(), value.encode())].into_iter().collect(),
+				child_info: child_info.clone(),
+			},
+		)]
+		.into_iter()
+		.collect(),
+	};
+	let backend =
+		InMemoryBackend::<sp_runtime::traits::BlakeTwo256>::from((storage, StateVersion::V0));
+	let root = *backend.root();
+	let proof = prove_child_read(backend, &child_info, [key]).unwrap();
+	let nodes = proof.into_nodes().into_iter().collect::<Vec<_>>();
+	(
+		CommitmentRoot::from_bytes(root.as_bytes()),
+		CommitmentProofBytes::try_from(nodes.encode()).unwrap(),
+	)
+}
+
+#[test]
+fn test_verify_membership_rejects_other_commitment_prefix() {
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let client = GrandpaClient::<HostFunctionsManager>::default();
+	let ctx = mock_context();
+	let client_state = ClientState { commitment_prefix: b"ibc/".to_vec(), ..client_state_at(100) };
+	let connection_id = ConnectionId::new(0);
+	let connection_end = ConnectionEnd::default();
+	let verify = |client_state: &ClientState<HostFunctionsManager>, prefix: &[u8]| {
+		let (root, proof) = child_trie_proof(
+			prefix,
+			ConnectionsPath(connection_id.clone()).into(),
+			connection_end.encode_vec().unwrap(),
+		);
+		client.verify_connection_state(
+			&ctx,
+			&client_id,
+			client_state,
+			Height::new(2000, 100),
+			&CommitmentPrefix::try_from(prefix.to_vec()).unwrap(),
+			&proof,
+			&root,
+			&connection_id,
+			&connection_end,
+		)
+	};
+
+	verify(&client_state, b"ibc/").unwrap();
+
+	// the proof is valid, but under a prefix the client isn't bound to
+	let err = verify(&client_state, b"other/").unwrap_err().to_string();
+	assert!(err.contains("MismatchedCommitmentPrefix"), "{err}");
+
+	// clients created without a commitment prefix accept any prefix, but can't be created anymore
+	client_state.verify_new_client_prefix().unwrap();
+	let legacy_client_state = ClientState { commitment_prefix: vec![], ..client_state };
+	verify(&legacy_client_state, b"other/").unwrap();
+	assert!(matches!(
+		legacy_client_state.verify_new_client_prefix(),
+		Err(crate::error::Error::EmptyCommitmentPrefix)
+	));
+}
@@ -14,7 +14,8 @@
 // limitations under the License.
 
 use beefy_light_client_primitives::{
-	ClientState as LightClientState, ParachainHeader, ParachainsUpdateProof,
+	error::BeefyClientError, ClientState as LightClientState, ParachainHeader,
+	ParachainsUpdateProof,
 };
 use codec::{Decode, Encode};
 use core::{fmt::Debug, marker::PhantomData};
@@ -24,7 +25,7 @@ use tendermint_proto::Protobuf;
 
 use crate::{
 	client_message::ClientMessage, client_state::ClientState, consensus_state::ConsensusState,
-	error::Error,
+	error::Error, misbehaviour::SignedCommitmentWithProof,
 };
 use ibc::{
 	core::{
@@ -129,7 +130,37 @@ where
 					.map_err(Error::from)?
 				}
 			},
-			ClientMessage::Misbehaviour(_) => unimplemented!(),
+			ClientMessage::Misbehaviour(misbehaviour) => {
+				if !misbehaviour.is_equivocation() {
+					Err(Error::Custom(
+						"Misbehaviour doesn't contain conflicting commitments for the same block"
+							.to_string(),
+					))?
+				}
+				// Both commitments share the same validator set id at this point
+				let validator_set_id =
+					misbehaviour.first.signed_commitment.commitment.validator_set_id;
+				let authority_set = match validator_set_id {
+					id if id == client_state.authority.id => &client_state.authority,
+					id if id == client_state.next_authority_set.id =>
+						&client_state.next_authority_set,
+					_ => Err(Error::Beefy(BeefyClientError::AuthoritySetMismatch {
+						current_set_id: client_state.authority.id,
+						next_set_id: client_state.next_authority_set.id,
+						commitment_set_id: validator_set_id,
+					}))?,
+				};
+				for SignedCommitmentWithProof { signed_commitment, authority_proof } in
+					[misbehaviour.first, misbehaviour.second]
+				{
+					beefy_client::verify_signed_commitment::<H>(
+						authority_set,
+						&signed_commitment,
+						authority_proof,
+					)
+					.map_err(Error::from)?
+				}
+			},
 		}
 		Ok(())
 	}
@@ -229,9 +260,9 @@ where
 					}
 				}
 			},
-			// todo: Beefy protocol hasn't yet defined it's equivocation protocol
-			// blocked on https://github.com/paritytech/grandpa-bridge-gadget/issues/101
-			ClientMessage::Misbehaviour(_) => {},
+			// Signatures on both commitments were checked in verify_client_message, two valid
+			// commitments for the same block with different payloads is an equivocation.
+			ClientMessage::Misbehaviour(misbehaviour) => return Ok(misbehaviour.is_equivocation()),
 		}
 
 		Ok(false)
@@ -17,12 +17,13 @@ use tendermint_proto::Protobuf;
 
 use crate::{
 	error::Error,
+	misbehaviour::Misbehaviour,
 	proto::{
 		client_message, BeefyAuthoritySet as RawBeefyAuthoritySet, BeefyMmrLeaf as RawBeefyMmrLeaf,
 		BeefyMmrLeafPartial as RawBeefyMmrLeafPartial, ClientMessage as RawClientMessage,
 		ClientStateUpdateProof as RawMmrUpdateProof, Commitment as RawCommitment,
-		CommitmentSignature, ConsensusStateUpdateProof, Header as RawBeefyHeader,
-		Misbehaviour as RawMisbehaviour, PayloadItem, SignedCommitment as RawSignedCommitment,
+		CommitmentSignature, ConsensusStateUpdateProof, Header as RawBeefyHeader, PayloadItem,
+		SignedCommitment as RawSignedCommitment,
 	},
 };
 use alloc::{format, vec, vec::Vec};
@@ -60,7 +61,7 @@ pub enum ClientMessage {
 	/// Header variant for updating the client
 	Header(BeefyHeader),
 	/// Misbehaviour variant for freezing the client.
-	Misbehaviour(()),
+	Misbehaviour(Misbehaviour),
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -105,6 +106,105 @@ pub fn merge_leaf_version(major: u8, minor: u8) -> u8 {
 	(major << 5) + minor
 }
 
+/// Convert a protobuf signed commitment into the light client's [`SignedCommitment`], keeping only
+/// the mmr root hash payload.
+pub(crate) fn signed_commitment_from_raw(
+	signed_commitment: RawSignedCommitment,
+) -> Result<SignedCommitment, Error> {
+	let commitment = signed_commitment
+		.commitment
+		.ok_or_else(|| Error::Custom(format!("Commitment is missing")))?;
+	let payload = {
+		commitment
+			.payload
+			.iter()
+			.filter_map(|item| {
+				if item.payload_id.as_slice() != MMR_ROOT_ID {
+					return None
+				}
+				let mut payload_id = [0u8; 2];
+				payload_id.copy_from_slice(&item.payload_id);
+				Some(Payload::from_single_entry(payload_id, item.payload_data.clone()))
+			})
+			.collect::<Vec<_>>()
+			.get(0)
+			.ok_or_else(|| Error::Custom(format!("Invalid payload, missing mmr root hash")))?
+			.clone()
+	};
+	let signatures = signed_commitment
+		.signatures
+		.into_iter()
+		.map(|commitment_sig| {
+			if commitment_sig.signature.len() != 65 {
+				return Err(Error::Custom(format!(
+					"Invalid signature length: {}",
+					commitment_sig.signature.len()
+				)))
+			}
+			Ok(SignatureWithAuthorityIndex {
+				signature: {
+					let mut sig = [0u8; 65];
+					sig.copy_from_slice(&commitment_sig.signature);
+					sig
+				},
+				index: commitment_sig.authority_index,
+			})
+		})
+		.collect::<Result<Vec<_>, Error>>()?;
+
+	Ok(SignedCommitment {
+		commitment: Commitment {
+			payload,
+			block_number: commitment.block_numer,
+			validator_set_id: commitment.validator_set_id,
+		},
+		signatures,
+	})
+}
+
+pub(crate) fn signed_commitment_to_raw(signed_commitment: SignedCommitment) -> RawSignedCommitment {
+	RawSignedCommitment {
+		commitment: Some(RawCommitment {
+			payload: vec![PayloadItem {
+				payload_id: MMR_ROOT_ID.to_vec(),
+				payload_data: signed_commitment
+					.commitment
+					.payload
+					.get_raw(&MMR_ROOT_ID)
+					.unwrap()
+					.clone(),
+			}],
+			block_numer: signed_commitment.commitment.block_number,
+			validator_set_id: signed_commitment.commitment.validator_set_id,
+		}),
+		signatures: signed_commitment
+			.signatures
+			.into_iter()
+			.map(|item| CommitmentSignature {
+				signature: item.signature.to_vec(),
+				authority_index: item.index,
+			})
+			.collect(),
+	}
+}
+
+pub(crate) fn authority_proof_from_raw(authority_proof: Vec<Vec<u8>>) -> Result<Vec<Hash>, Error> {
+	authority_proof
+		.into_iter()
+		.map(|item| {
+			if item.len() != 32 {
+				return Err(Error::Custom(format!(
+					"Invalid authorities proof item with len: {}",
+					item.len()
+				)))
+			}
+			let mut dest = [0u8; 32];
+			dest.copy_from_slice(&item);
+			Ok(dest)
+		})
+		.collect()
+}
+
 impl TryFrom<RawClientMessage> for ClientMessage {
 	type Error = Error;
 
@@ -194,59 +294,10 @@ impl TryFrom<RawClientMessage> for ClientMessage {
 					.flatten();
 
 				let mmr_update_proof = if let Some(mmr_update) = raw_header.client_state {
-					let commitment = mmr_update
-						.signed_commitment
-						.as_ref()
-						.ok_or_else(|| Error::Custom(format!("Signed commitment is missing")))?
-						.commitment
-						.as_ref()
-						.ok_or_else(|| Error::Custom(format!("Commitment is missing")))?;
-					let payload = {
-						commitment
-							.payload
-							.iter()
-							.filter_map(|item| {
-								if item.payload_id.as_slice() != MMR_ROOT_ID {
-									return None
-								}
-								let mut payload_id = [0u8; 2];
-								payload_id.copy_from_slice(&item.payload_id);
-								Some(Payload::from_single_entry(
-									payload_id,
-									item.payload_data.clone(),
-								))
-							})
-							.collect::<Vec<_>>()
-							.get(0)
-							.ok_or_else(|| {
-								Error::Custom(format!("Invalid payload, missing mmr root hash"))
-							})?
-							.clone()
-					};
-					let block_number = commitment.block_numer;
-					let validator_set_id = commitment.validator_set_id;
-					let signatures = mmr_update
-						.signed_commitment
-						.ok_or_else(|| Error::Custom(format!("Signed Commiment is missing")))?
-						.signatures
-						.into_iter()
-						.map(|commitment_sig| {
-							if commitment_sig.signature.len() != 65 {
-								return Err(Error::Custom(format!(
-									"Invalid signature length: {}",
-									commitment_sig.signature.len()
-								)))
-							}
-							Ok(SignatureWithAuthorityIndex {
-								signature: {
-									let mut sig = [0u8; 65];
-									sig.copy_from_slice(&commitment_sig.signature);
-									sig
-								},
-								index: commitment_sig.authority_index,
-							})
-						})
-						.collect::<Result<Vec<_>, Error>>()?;
+					let signed_commitment =
+						signed_commitment_from_raw(mmr_update.signed_commitment.ok_or_else(
+							|| Error::Custom(format!("Signed commitment is missing")),
+						)?)?;
 
 					let mmr_leaf = mmr_update
 						.mmr_leaf
@@ -258,10 +309,7 @@ impl TryFrom<RawClientMessage> for ClientMessage {
 						})?;
 
 					Some(MmrUpdateProof {
-						signed_commitment: SignedCommitment {
-							commitment: Commitment { payload, block_number, validator_set_id },
-							signatures,
-						},
+						signed_commitment,
 						latest_mmr_leaf: MmrLeaf {
 							version: {
 								let (major, minor) =
@@ -298,21 +346,7 @@ impl TryFrom<RawClientMessage> for ClientMessage {
 								})
 								.collect::<Result<Vec<_>, Error>>()?,
 						},
-						authority_proof: mmr_update
-							.authorities_proof
-							.into_iter()
-							.map(|item| {
-								if item.len() != 32 {
-									return Err(Error::Custom(format!(
-										"Invalid authorities proof item with len: {}",
-										item.len()
-									)))
-								}
-								let mut dest = [0u8; 32];
-								dest.copy_from_slice(&item);
-								Ok(dest)
-							})
-							.collect::<Result<Vec<_>, Error>>()?,
+						authority_proof: authority_proof_from_raw(mmr_update.authorities_proof)?,
 					})
 				} else {
 					None
@@ -320,7 +354,8 @@ impl TryFrom<RawClientMessage> for ClientMessage {
 
 				ClientMessage::Header(BeefyHeader { headers_with_proof, mmr_update_proof })
 			},
-			client_message::Message::Misbehaviour(_) => ClientMessage::Misbehaviour(()),
+			client_message::Message::Misbehaviour(raw_misbehaviour) =>
+				ClientMessage::Misbehaviour(raw_misbehaviour.try_into()?),
 		};
 
 		Ok(message)
@@ -420,37 +455,9 @@ impl From<ClientMessage> for RawClientMessage {
 								.into_iter()
 								.map(|item| item.encode())
 								.collect(),
-							signed_commitment: Some(RawSignedCommitment {
-								commitment: Some(RawCommitment {
-									payload: vec![PayloadItem {
-										payload_id: MMR_ROOT_ID.to_vec(),
-										payload_data: mmr_update
-											.signed_commitment
-											.commitment
-											.payload
-											.get_raw(&MMR_ROOT_ID)
-											.unwrap()
-											.clone(),
-									}],
-									block_numer: mmr_update
-										.signed_commitment
-										.commitment
-										.block_number,
-									validator_set_id: mmr_update
-										.signed_commitment
-										.commitment
-										.validator_set_id,
-								}),
-								signatures: mmr_update
-									.signed_commitment
-									.signatures
-									.into_iter()
-									.map(|item| CommitmentSignature {
-										signature: item.signature.to_vec(),
-										authority_index: item.index,
-									})
-									.collect(),
-							}),
+							signed_commitment: Some(signed_commitment_to_raw(
+								mmr_update.signed_commitment,
+							)),
 							authorities_proof: mmr_update
 								.authority_proof
 								.into_iter()
@@ -462,8 +469,8 @@ impl From<ClientMessage> for RawClientMessage {
 					},
 				})),
 			},
-			ClientMessage::Misbehaviour(_) => RawClientMessage {
-				message: Some(client_message::Message::Misbehaviour(RawMisbehaviour {})),
+			ClientMessage::Misbehaviour(misbehaviour) => RawClientMessage {
+				message: Some(client_message::Message::Misbehaviour(misbehaviour.into())),
 			},
 		}
 	}
@@ -33,7 +33,7 @@ use ibc::{
 	timestamp::Timestamp,
 	Height,
 };
-use light_client_common::RelayChain;
+use light_client_common::{RelayChain, RelayChainPeriods};
 
 /// Protobuf type url for Beefy ClientState
 pub const BEEFY_CLIENT_STATE_TYPE_URL: &str = "/ibc.lightclients.beefy.v1.ClientState";
@@ -58,6 +58,8 @@ pub struct ClientState<H> {
 	pub authority: BeefyNextAuthoritySet<H256>,
 	/// authorities for the next round
 	pub next_authority_set: BeefyNextAuthoritySet<H256>,
+	/// Trusting period overriding the relay chain's default one, see [`RelayChainPeriods`].
+	pub custom_trusting_period: Option<Duration>,
 	/// Phantom type
 	pub _phantom: PhantomData<H>,
 }
@@ -93,6 +95,7 @@ impl<H: Clone> ClientState<H> {
 			relay_chain,
 			latest_para_height,
 			para_id,
+			custom_trusting_period: None,
 			_phantom: PhantomData,
 		})
 	}
@@ -214,10 +217,25 @@ impl<H> ClientState<H> {
 		self
 	}
 
+	/// Unbonding and trusting periods of the client. Falls back to the relay chain's defaults if
+	/// the custom trusting period isn't shorter than the unbonding period.
+	pub fn periods(&self) -> RelayChainPeriods {
+		RelayChainPeriods::new(self.relay_chain, self.custom_trusting_period)
+			.unwrap_or_else(|_| self.relay_chain.into())
+	}
+
+	pub fn unbonding_period(&self) -> Duration {
+		self.periods().unbonding_period()
+	}
+
+	pub fn trusting_period(&self) -> Duration {
+		self.periods().trusting_period()
+	}
+
 	/// Check if the state is expired when `elapsed` time has passed since the latest consensus
 	/// state timestamp
 	pub fn expired(&self, elapsed: Duration) -> bool {
-		elapsed > self.relay_chain.trusting_period()
+		elapsed > self.trusting_period()
 	}
 }
 
@@ -293,7 +311,9 @@ impl<H> TryFrom<RawClientState> for ClientState<H> {
 			.ok_or_else(|| Error::Custom(format!("Next authority set is missing")))?;
 
 		let mmr_root_hash = H256::decode(&mut &*raw.mmr_root_hash)?;
-		let relay_chain = RelayChain::from_i32(raw.relay_chain)?;
+		let relay_chain = RelayChain::from_raw(raw.relay_chain, raw.relay_chain_unbonding_period)?;
+		let custom_trusting_period = raw.custom_trusting_period.map(Duration::from_secs);
+		RelayChainPeriods::new(relay_chain, custom_trusting_period)?;
 		let chain_id = ChainId::new(relay_chain.to_string(), raw.para_id.into());
 
 		Ok(Self {
@@ -306,6 +326,7 @@ impl<H> TryFrom<RawClientState> for ClientState<H> {
 			relay_chain,
 			latest_para_height: raw.latest_para_height,
 			para_id: raw.para_id,
+			custom_trusting_period,
 			_phantom: Default::default(),
 		})
 	}
@@ -329,9 +350,13 @@ impl<H> From<ClientState<H>> for RawClientState {
 				len: client_state.next_authority_set.len,
 				authority_root: client_state.next_authority_set.root.encode(),
 			}),
-			relay_chain: client_state.relay_chain as i32,
+			relay_chain: client_state.relay_chain.to_i32(),
+			relay_chain_unbonding_period: client_state.relay_chain.custom_unbonding_period(),
 			para_id: client_state.para_id,
 			latest_para_height: client_state.latest_para_height,
+			custom_trusting_period: client_state
+				.custom_trusting_period
+				.map(|trusting_period| trusting_period.as_secs()),
 		}
 	}
 }
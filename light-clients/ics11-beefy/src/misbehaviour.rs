@@ -12,3 +12,103 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use crate::{
+	client_message::{
+		authority_proof_from_raw, signed_commitment_from_raw, signed_commitment_to_raw,
+	},
+	error::Error,
+	proto::{
+		Misbehaviour as RawMisbehaviour, SignedCommitmentWithProof as RawSignedCommitmentWithProof,
+	},
+};
+use alloc::{format, vec::Vec};
+use beefy_light_client_primitives::{Hash, SignedCommitment};
+use codec::{Decode, Encode};
+use tendermint_proto::Protobuf;
+
+/// Protobuf type url for Beefy misbehaviour
+pub const BEEFY_MISBEHAVIOUR_TYPE_URL: &str = "/ibc.lightclients.beefy.v1.Misbehaviour";
+
+/// A signed commitment alongside the merkle multi-proof of its signers in the authority set.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct SignedCommitmentWithProof {
+	/// Signed commitment
+	pub signed_commitment: SignedCommitment,
+	/// Proof for the signers' addresses in the authority set that signed the commitment
+	pub authority_proof: Vec<Hash>,
+}
+
+/// Evidence of BEEFY equivocation: two commitments for the same block, signed by the same
+/// authority set, but with different payloads.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct Misbehaviour {
+	/// first signed commitment
+	pub first: SignedCommitmentWithProof,
+	/// second signed commitment
+	pub second: SignedCommitmentWithProof,
+}
+
+impl Misbehaviour {
+	/// Returns true if both commitments are for the same block and authority set, but commit to
+	/// different payloads.
+	pub fn is_equivocation(&self) -> bool {
+		let first = &self.first.signed_commitment.commitment;
+		let second = &self.second.signed_commitment.commitment;
+		first.block_number == second.block_number &&
+			first.validator_set_id == second.validator_set_id &&
+			first.payload != second.payload
+	}
+}
+
+impl TryFrom<RawSignedCommitmentWithProof> for SignedCommitmentWithProof {
+	type Error = Error;
+
+	fn try_from(raw: RawSignedCommitmentWithProof) -> Result<Self, Self::Error> {
+		Ok(Self {
+			signed_commitment: signed_commitment_from_raw(
+				raw.signed_commitment
+					.ok_or_else(|| Error::Custom(format!("Signed commitment is missing")))?,
+			)?,
+			authority_proof: authority_proof_from_raw(raw.authorities_proof)?,
+		})
+	}
+}
+
+impl From<SignedCommitmentWithProof> for RawSignedCommitmentWithProof {
+	fn from(value: SignedCommitmentWithProof) -> Self {
+		RawSignedCommitmentWithProof {
+			signed_commitment: Some(signed_commitment_to_raw(value.signed_commitment)),
+			authorities_proof: value
+				.authority_proof
+				.into_iter()
+				.map(|item| item.to_vec())
+				.collect(),
+		}
+	}
+}
+
+impl Protobuf<RawMisbehaviour> for Misbehaviour {}
+
+impl TryFrom<RawMisbehaviour> for Misbehaviour {
+	type Error = Error;
+
+	fn try_from(raw: RawMisbehaviour) -> Result<Self, Self::Error> {
+		Ok(Self {
+			first: raw
+				.first
+				.ok_or_else(|| Error::Custom(format!("First signed commitment is missing")))?
+				.try_into()?,
+			second: raw
+				.second
+				.ok_or_else(|| Error::Custom(format!("Second signed commitment is missing")))?
+				.try_into()?,
+		})
+	}
+}
+
+impl From<Misbehaviour> for RawMisbehaviour {
+	fn from(value: Misbehaviour) -> Self {
+		RawMisbehaviour { first: Some(value.first.into()), second: Some(value.second.into()) }
+	}
+}
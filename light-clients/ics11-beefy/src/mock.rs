@@ -56,17 +56,21 @@ impl beefy_light_client_primitives::HostFunctions for HostFunctionsManager {
 	fn keccak_256(input: &[u8]) -> [u8; 32] {
 		beefy_prover::Crypto::keccak_256(input)
 	}
-
-	fn secp256k1_ecdsa_recover_compressed(
-		signature: &[u8; 65],
-		value: &[u8; 32],
-	) -> Option<Vec<u8>> {
-		beefy_prover::Crypto::secp256k1_ecdsa_recover_compressed(signature, value)
-	}
 }
 
 impl light_client_common::HostFunctions for HostFunctionsManager {
 	type BlakeTwo256 = BlakeTwo256;
+	type Keccak256 = sp_runtime::traits::Keccak256;
+	type Sha256 = light_client_common::Sha256Hasher;
+
+	fn secp256k1_ecdsa_recover_compressed(
+		msg: &[u8; 32],
+		signature: &[u8; 65],
+	) -> Result<[u8; 33], light_client_common::EcdsaRecoveryError> {
+		<beefy_prover::Crypto as light_client_common::HostFunctions>::secp256k1_ecdsa_recover_compressed(
+			msg, signature,
+		)
+	}
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, ClientDef)]
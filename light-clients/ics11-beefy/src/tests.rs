@@ -14,20 +14,28 @@
 // limitations under the License.
 
 use crate::{
+	client_def::BeefyClient,
 	client_message::{
 		BeefyHeader, ClientMessage, ParachainHeader as BeefyParachainHeader,
 		ParachainHeadersWithProof,
 	},
 	client_state::{ClientState as BeefyClientState, ClientState},
 	consensus_state::ConsensusState,
+	misbehaviour::{Misbehaviour, SignedCommitmentWithProof},
 	mock::{
 		AnyClientMessage, AnyClientState, AnyConsensusState, HostFunctionsManager, MockClientTypes,
 	},
 };
-use beefy_light_client_primitives::{EncodedVersionedFinalityProof, NodesUtils, PartialMmrLeaf};
-use beefy_primitives::VersionedFinalityProof;
+use beefy_light_client_primitives::{
+	BeefyNextAuthoritySet, EncodedVersionedFinalityProof, MerkleHasher, NodesUtils, PartialMmrLeaf,
+	SignedCommitment,
+};
+use beefy_primitives::{known_payloads::MMR_ROOT_ID, Commitment, Payload, VersionedFinalityProof};
 use beefy_prover::{
-	helpers::{fetch_timestamp_extrinsic_with_proof, TimeStampExtWithProof},
+	helpers::{
+		fetch_timestamp_extrinsic_with_proof, hash_authority_addresses, prove_authority_set,
+		AuthorityProofWithSignatures, TimeStampExtWithProof,
+	},
 	Prover,
 };
 use codec::{Decode, Encode};
@@ -36,6 +44,7 @@ use hyperspace_core::substrate::DefaultConfig as PolkadotConfig;
 use ibc::{
 	core::{
 		ics02_client::{
+			client_def::ClientDef,
 			client_state::ClientState as _,
 			context::{ClientKeeper, ClientReader},
 			handler::{dispatch, ClientResult::Update},
@@ -51,9 +60,11 @@ use ibc::{
 	test_utils::get_dummy_account_id,
 	Height,
 };
-use light_client_common::config::RuntimeStorage;
+use light_client_common::{config::RuntimeStorage, RelayChain};
+use sp_core::{ecdsa, keccak_256, Pair, H256};
 use std::time::Duration;
 use subxt::rpc::{rpc_params, Subscription};
+use tendermint_proto::Protobuf;
 
 #[tokio::test]
 #[ignore]
@@ -306,3 +317,178 @@ async fn test_continuous_update_of_beefy_client() {
 		}
 	}
 }
+
+/// Synthetic BEEFY authority set used to sign commitments in misbehaviour tests.
+struct TestAuthorities {
+	pairs: Vec<ecdsa::Pair>,
+	address_hashes: Vec<[u8; 32]>,
+}
+
+impl TestAuthorities {
+	fn new(seed: u8, count: usize) -> Self {
+		let pairs = (0..count)
+			.map(|i| ecdsa::Pair::from_seed(&[seed + i as u8; 32]))
+			.collect::<Vec<_>>();
+		let address_hashes =
+			hash_authority_addresses(pairs.iter().map(|pair| pair.public().encode()).collect())
+				.unwrap();
+		Self { pairs, address_hashes }
+	}
+
+	fn authority_set(&self, id: u64) -> BeefyNextAuthoritySet<H256> {
+		let root = rs_merkle::MerkleTree::<MerkleHasher<HostFunctionsManager>>::from_leaves(
+			&self.address_hashes,
+		)
+		.root()
+		.unwrap();
+		BeefyNextAuthoritySet { id, len: self.pairs.len() as u32, root: root.into() }
+	}
+
+	fn sign(
+		&self,
+		block_number: u32,
+		validator_set_id: u64,
+		mmr_root: H256,
+	) -> SignedCommitmentWithProof {
+		let commitment = Commitment {
+			payload: Payload::from_single_entry(MMR_ROOT_ID, mmr_root.encode()),
+			block_number,
+			validator_set_id,
+		};
+		let commitment_hash = keccak_256(&commitment.encode());
+		let signed_commitment = beefy_primitives::SignedCommitment {
+			signatures: self
+				.pairs
+				.iter()
+				.map(|pair| Some(pair.sign_prehashed(&commitment_hash).into()))
+				.collect(),
+			commitment,
+		};
+		let AuthorityProofWithSignatures { authority_proof, signatures } =
+			prove_authority_set(&signed_commitment, self.address_hashes.clone()).unwrap();
+		SignedCommitmentWithProof {
+			signed_commitment: SignedCommitment {
+				commitment: signed_commitment.commitment,
+				signatures,
+			},
+			authority_proof,
+		}
+	}
+}
+
+fn misbehaviour_client_state(
+	authorities: &TestAuthorities,
+) -> BeefyClientState<HostFunctionsManager> {
+	BeefyClientState::new(
+		RelayChain::Rococo,
+		2000,
+		10,
+		H256::zero(),
+		100,
+		authorities.authority_set(0),
+		authorities.authority_set(1),
+	)
+	.unwrap()
+}
+
+fn misbehaviour_ctx() -> MockContext<MockClientTypes> {
+	MockContext::<MockClientTypes>::new(
+		ChainId::new("mockgaiaA".to_string(), 1),
+		MockHostType::Mock,
+		5,
+		Height::new(1, 11),
+	)
+}
+
+#[test]
+fn should_freeze_client_on_conflicting_commitments() {
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let ctx = misbehaviour_ctx();
+	let authorities = TestAuthorities::new(1, 4);
+	let client_state = misbehaviour_client_state(&authorities);
+	let client = BeefyClient::<HostFunctionsManager>::default();
+
+	let misbehaviour = ClientMessage::Misbehaviour(Misbehaviour {
+		first: authorities.sign(120, 0, H256::repeat_byte(1)),
+		second: authorities.sign(120, 0, H256::repeat_byte(2)),
+	});
+
+	client
+		.verify_client_message(&ctx, client_id.clone(), client_state.clone(), misbehaviour.clone())
+		.unwrap();
+	assert!(client
+		.check_for_misbehaviour(&ctx, client_id, client_state.clone(), misbehaviour.clone())
+		.unwrap());
+
+	let client_state = client.update_state_on_misbehaviour(client_state, misbehaviour).unwrap();
+	assert_eq!(client_state.frozen_height, Some(Height::new(2000, 10)));
+}
+
+#[test]
+fn should_reject_non_conflicting_or_unknown_commitments() {
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let ctx = misbehaviour_ctx();
+	let authorities = TestAuthorities::new(1, 4);
+	let client_state = misbehaviour_client_state(&authorities);
+	let client = BeefyClient::<HostFunctionsManager>::default();
+
+	let verify = |first: SignedCommitmentWithProof, second: SignedCommitmentWithProof| {
+		client.verify_client_message(
+			&ctx,
+			client_id.clone(),
+			client_state.clone(),
+			ClientMessage::Misbehaviour(Misbehaviour { first, second }),
+		)
+	};
+
+	// identical payloads
+	assert!(verify(
+		authorities.sign(120, 0, H256::repeat_byte(1)),
+		authorities.sign(120, 0, H256::repeat_byte(1))
+	)
+	.is_err());
+	// different block numbers
+	assert!(verify(
+		authorities.sign(120, 0, H256::repeat_byte(1)),
+		authorities.sign(121, 0, H256::repeat_byte(2))
+	)
+	.is_err());
+	// different validator set ids
+	assert!(verify(
+		authorities.sign(120, 0, H256::repeat_byte(1)),
+		authorities.sign(120, 1, H256::repeat_byte(2))
+	)
+	.is_err());
+	// authority set unknown to the client
+	assert!(verify(
+		authorities.sign(120, 5, H256::repeat_byte(1)),
+		authorities.sign(120, 5, H256::repeat_byte(2))
+	)
+	.is_err());
+	// commitments signed by another set of authorities
+	let other_authorities = TestAuthorities::new(10, 4);
+	assert!(verify(
+		other_authorities.sign(120, 0, H256::repeat_byte(1)),
+		other_authorities.sign(120, 0, H256::repeat_byte(2))
+	)
+	.is_err());
+}
+
+#[test]
+fn should_encode_and_decode_misbehaviour() {
+	let authorities = TestAuthorities::new(1, 4);
+	let misbehaviour = Misbehaviour {
+		first: authorities.sign(120, 0, H256::repeat_byte(1)),
+		second: authorities.sign(120, 0, H256::repeat_byte(2)),
+	};
+
+	let decoded = <Misbehaviour as Decode>::decode(&mut &*Encode::encode(&misbehaviour)).unwrap();
+	assert_eq!(decoded, misbehaviour);
+
+	let decoded = Misbehaviour::decode_vec(&misbehaviour.encode_vec().unwrap()).unwrap();
+	assert_eq!(decoded, misbehaviour);
+
+	let client_message = ClientMessage::Misbehaviour(misbehaviour);
+	let decoded = ClientMessage::decode_vec(&client_message.encode_vec().unwrap()).unwrap();
+	assert_eq!(decoded, client_message);
+}
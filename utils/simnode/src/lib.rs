@@ -141,6 +141,9 @@ mod tests {
 				para_id: 100,
 				current_set_id: 1,
 				current_authorities: Default::default(),
+				commitment_prefix: b"ibc/".to_vec(),
+				custom_trusting_period: None,
+				max_consensus_states: None,
 				_phantom: Default::default(),
 			};
 